@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE posts ADD COLUMN slug VARCHAR(255) NULL UNIQUE;
+
+-- 既存の投稿にはIDから作成したスラッグを設定する
+UPDATE posts SET slug = CONCAT('post-', post_id);
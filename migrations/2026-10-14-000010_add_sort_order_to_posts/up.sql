@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE posts ADD COLUMN sort_order INT NOT NULL DEFAULT 0;
+
+-- 既存の投稿は作成順に並ぶようにする
+UPDATE posts SET sort_order = post_id;
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE comments DROP COLUMN edit_count;
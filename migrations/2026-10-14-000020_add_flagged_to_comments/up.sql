@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE comments ADD COLUMN flagged BOOLEAN NOT NULL DEFAULT FALSE;
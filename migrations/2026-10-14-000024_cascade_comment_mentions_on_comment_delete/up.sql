@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- コメントを削除した際にメンションも削除されるようにする
+ALTER TABLE comment_mentions
+    DROP FOREIGN KEY comment_mentions_ibfk_1,
+    ADD CONSTRAINT fk_comment_mentions_comment_id
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE;
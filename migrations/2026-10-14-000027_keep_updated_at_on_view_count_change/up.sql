@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- PostgreSQLのトリガーの変更に合わせたマイグレーション
+-- MySQLでは increment_view_count で updated_at を明示的に指定して ON UPDATE CURRENT_TIMESTAMP を止めるため、スキーマの変更はない
+DO 0;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE comment_mentions
+    DROP FOREIGN KEY fk_comment_mentions_mentioned_user_id,
+    ADD CONSTRAINT comment_mentions_ibfk_2
+        FOREIGN KEY (mentioned_user_id) REFERENCES users(user_id);
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- メンションされたユーザーを削除した際にメンションも削除されるようにする
+ALTER TABLE comment_mentions
+    DROP FOREIGN KEY comment_mentions_ibfk_2,
+    ADD CONSTRAINT fk_comment_mentions_mentioned_user_id
+        FOREIGN KEY (mentioned_user_id) REFERENCES users(user_id) ON DELETE CASCADE;
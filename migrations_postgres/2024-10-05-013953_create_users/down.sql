@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE comments;
+DROP TABLE posts;
+DROP TABLE users;
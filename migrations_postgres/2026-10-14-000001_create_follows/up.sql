@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE follows (
+    follow_id SERIAL PRIMARY KEY,
+    follower_id INT NOT NULL,
+    followee_id INT NOT NULL,
+    UNIQUE (follower_id, followee_id),
+    FOREIGN KEY (follower_id) REFERENCES users(user_id),
+    FOREIGN KEY (followee_id) REFERENCES users(user_id)
+);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER set_comments_updated_at ON comments;
+DROP TRIGGER set_posts_updated_at ON posts;
+DROP TRIGGER set_users_updated_at ON users;
+DROP FUNCTION brog_set_updated_at();
+ALTER TABLE comments DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE posts DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE users DROP COLUMN created_at, DROP COLUMN updated_at;
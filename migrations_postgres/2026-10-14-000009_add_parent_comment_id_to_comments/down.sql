@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE comments DROP CONSTRAINT fk_comments_parent_comment_id;
+ALTER TABLE comments DROP COLUMN parent_comment_id;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE comments
+    ADD COLUMN parent_comment_id INT NULL,
+    ADD CONSTRAINT fk_comments_parent_comment_id FOREIGN KEY (parent_comment_id) REFERENCES comments(comment_id);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE comments ADD COLUMN edited BOOLEAN NOT NULL DEFAULT FALSE;
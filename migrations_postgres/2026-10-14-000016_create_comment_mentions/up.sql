@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE comment_mentions (
+    comment_id INT NOT NULL,
+    mentioned_user_id INT NOT NULL,
+    PRIMARY KEY (comment_id, mentioned_user_id),
+    FOREIGN KEY (comment_id) REFERENCES comments(comment_id),
+    FOREIGN KEY (mentioned_user_id) REFERENCES users(user_id)
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE posts ADD COLUMN needs_review BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE comments ADD COLUMN edit_count INT NOT NULL DEFAULT 0;
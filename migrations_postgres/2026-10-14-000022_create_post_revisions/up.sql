@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE post_revisions (
+    revision_id SERIAL PRIMARY KEY,
+    post_id INT NOT NULL,
+    post_body TEXT NOT NULL,
+    created_at TIMESTAMP(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    FOREIGN KEY (post_id) REFERENCES posts(post_id) ON DELETE CASCADE
+);
+
+-- 既存の投稿は現在の本文を最初の版とする
+INSERT INTO post_revisions (post_id, post_body)
+SELECT post_id, post_body FROM posts;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE comment_mentions
+    DROP CONSTRAINT fk_comment_mentions_comment_id,
+    ADD CONSTRAINT comment_mentions_comment_id_fkey
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id);
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- コメントを削除した際にメンションも削除されるようにする
+ALTER TABLE comment_mentions
+    DROP CONSTRAINT comment_mentions_comment_id_fkey,
+    ADD CONSTRAINT fk_comment_mentions_comment_id
+        FOREIGN KEY (comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER set_posts_updated_at ON posts;
+CREATE TRIGGER set_posts_updated_at BEFORE UPDATE ON posts
+    FOR EACH ROW EXECUTE FUNCTION brog_set_updated_at();
+DROP FUNCTION brog_set_posts_updated_at();
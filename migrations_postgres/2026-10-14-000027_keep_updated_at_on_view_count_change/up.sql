@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- 閲覧数だけが変わった場合は posts の updated_at を更新しない
+CREATE FUNCTION brog_set_posts_updated_at() RETURNS trigger AS $$
+DECLARE
+    compared posts%ROWTYPE;
+BEGIN
+    compared := NEW;
+    compared.view_count := OLD.view_count;
+    IF compared IS DISTINCT FROM OLD AND NEW.updated_at IS NOT DISTINCT FROM OLD.updated_at THEN
+        NEW.updated_at := CURRENT_TIMESTAMP(6);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER set_posts_updated_at ON posts;
+CREATE TRIGGER set_posts_updated_at BEFORE UPDATE ON posts
+    FOR EACH ROW EXECUTE FUNCTION brog_set_posts_updated_at();
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+CREATE UNIQUE INDEX idx_users_name_unique ON users (name);
@@ -0,0 +1,82 @@
+//! フィクスチャモジュール
+//!
+//! ローカル開発用のサンプルデータを投入する関数を提供します。
+//! 新しく開発に参加した人が、空のデータベースからすぐに動作を確認できるようにするためのものです。
+//! テスト間でデータベースを初期状態に戻すための `truncate_all` もここに置きます。
+
+use crate::db::queries;
+use crate::db::schema;
+use crate::db::DbConnection;
+use crate::email::Email;
+use crate::error::BrogError;
+use crate::password;
+use diesel::prelude::*;
+
+/// サンプルデータが投入済みかを判定するためのユーザーのメールアドレス
+pub const DEMO_SENTINEL_EMAIL: &str = "alice@example.com";
+
+/// サンプルユーザーのパスワード(全員共通)
+const DEMO_PASSWORD: &str = "demo-password";
+
+/// サンプルデータを投入する関数
+///
+/// 数人のユーザーと、その投稿・コメントを1つのトランザクションで作成します。
+/// `DEMO_SENTINEL_EMAIL` のユーザーが既に存在する場合は投入済みとみなし、何もしません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+pub fn seed_demo_data(conn: &mut DbConnection) -> Result<(), BrogError> {
+    let password_hash = password::hash_password(DEMO_PASSWORD)?;
+
+    conn.transaction(|conn| {
+        let seeded = diesel::select(diesel::dsl::exists(
+            schema::users::table.filter(schema::users::email.eq(DEMO_SENTINEL_EMAIL)),
+        ))
+        .get_result::<bool>(conn)?;
+        if seeded {
+            return Ok(());
+        }
+
+        let alice = queries::create_user(conn, "alice", &Email::try_from(DEMO_SENTINEL_EMAIL)?, password_hash.as_str())?;
+        let bob = queries::create_user(conn, "bob", &Email::try_from("bob@example.com")?, password_hash.as_str())?;
+        let carol = queries::create_user(conn, "carol", &Email::try_from("carol@example.com")?, password_hash.as_str())?;
+
+        let hello = queries::create_post(conn, "Hello, BrogApp", "This is the first demo post.", true, alice.user_id())?;
+        let rust = queries::create_post(conn, "Why I like Rust", "Ownership makes refactoring fearless.", true, bob.user_id())?;
+        queries::create_post(conn, "Unfinished draft", "This post is not published yet.", false, alice.user_id())?;
+
+        queries::create_comment(conn, bob.user_id(), hello.post_id(), "Welcome aboard!")?;
+        queries::create_comment(conn, carol.user_id(), hello.post_id(), "Looking forward to more posts.")?;
+        queries::create_comment(conn, alice.user_id(), rust.post_id(), "Agreed, the compiler has my back.")?;
+
+        Ok(())
+    })
+}
+
+/// すべてのテーブルのデータを削除する関数
+///
+/// テストの実行ごとにデータベースを空の状態に戻すために使用します。
+/// 外部キー制約に違反しないよう、参照している側のテーブルから順に1つのトランザクションで削除します。
+/// 本番のデータベースで誤って呼び出さないよう、テスト時と `test-utils` フィーチャー有効時のみ使用できます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+#[cfg(any(test, feature = "test-utils"))]
+pub fn truncate_all(conn: &mut DbConnection) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        diesel::delete(schema::comment_mentions::table).execute(conn)?;
+        // 返信が親コメントを参照しているため、先に参照を外してから削除する
+        diesel::update(schema::comments::table)
+            .set(schema::comments::parent_comment_id.eq(None::<i32>))
+            .execute(conn)?;
+        diesel::delete(schema::comments::table).execute(conn)?;
+        diesel::delete(schema::post_likes::table).execute(conn)?;
+        diesel::delete(schema::post_tags::table).execute(conn)?;
+        diesel::delete(schema::posts::table).execute(conn)?;
+        diesel::delete(schema::tags::table).execute(conn)?;
+        diesel::delete(schema::password_resets::table).execute(conn)?;
+        diesel::delete(schema::follows::table).execute(conn)?;
+        diesel::delete(schema::users::table).execute(conn)?;
+        Ok(())
+    })
+}
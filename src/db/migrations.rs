@@ -0,0 +1,84 @@
+//! マイグレーションモジュール
+//!
+//! `migrations` ディレクトリ(`postgres` フィーチャー有効時は `migrations_postgres`)のマイグレーションをバイナリに埋め込み、
+//! アプリケーションの起動時に適用できるようにします。
+//! Diesel CLIを使わずにデータベースのスキーマを最新の状態にするためのものです。
+
+use crate::db::DbConnection;
+use crate::error::BrogError;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+/// バイナリに埋め込んだマイグレーション
+/// `unique-usernames` フィーチャー用のマイグレーションは `UNIQUE_USERNAMES_MIGRATIONS` に分けている。
+#[cfg(not(feature = "postgres"))]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+/// バイナリに埋め込んだマイグレーション(PostgreSQL用)
+/// `migrations` と同じバージョンのマイグレーションをPostgreSQLの構文で記述したもの。
+#[cfg(feature = "postgres")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+
+/// `unique-usernames` フィーチャー有効時に追加で適用するマイグレーション
+/// ユーザー名に一意制約を付ける。
+#[cfg(all(feature = "unique-usernames", not(feature = "postgres")))]
+pub const UNIQUE_USERNAMES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_unique_usernames");
+/// `unique-usernames` フィーチャー有効時に追加で適用するマイグレーション(PostgreSQL用)
+#[cfg(all(feature = "unique-usernames", feature = "postgres"))]
+pub const UNIQUE_USERNAMES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_unique_usernames_postgres");
+
+/// 未適用のマイグレーションをすべて適用する関数
+///
+/// 適用済みのマイグレーションは `__diesel_schema_migrations` テーブルで管理されるため、何度呼び出しても問題ありません。
+/// `unique-usernames` フィーチャー有効時は `UNIQUE_USERNAMES_MIGRATIONS` も適用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - マイグレーションの適用に失敗した場合は `BrogError::Migration`
+pub fn run_pending_migrations(conn: &mut DbConnection) -> Result<(), BrogError> {
+    conn.run_pending_migrations(MIGRATIONS).map_err(BrogError::Migration)?;
+    #[cfg(feature = "unique-usernames")]
+    conn.run_pending_migrations(UNIQUE_USERNAMES_MIGRATIONS).map_err(BrogError::Migration)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    fn migration_names(dir: &str) -> BTreeSet<String> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        std::fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| entry.file_type().unwrap().is_dir())
+            .map(|entry| entry.file_name().into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn postgres_migrations_mirror_mysql_migrations() {
+        assert_eq!(migration_names("migrations"), migration_names("migrations_postgres"));
+        assert_eq!(
+            migration_names("migrations_unique_usernames"),
+            migration_names("migrations_unique_usernames_postgres")
+        );
+    }
+
+    #[test]
+    fn every_migration_has_up_and_down() {
+        for dir in [
+            "migrations",
+            "migrations_postgres",
+            "migrations_unique_usernames",
+            "migrations_unique_usernames_postgres",
+        ] {
+            for name in migration_names(dir) {
+                let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir).join(&name);
+                assert!(path.join("up.sql").is_file(), "{}/{} has no up.sql", dir, name);
+                assert!(path.join("down.sql").is_file(), "{}/{} has no down.sql", dir, name);
+            }
+        }
+    }
+}
@@ -1,2 +1,54 @@
+pub mod fixtures; // fixtures.rsを公開
+pub mod migrations; // migrations.rsを公開
+pub mod pagination; // pagination.rsを公開
 pub mod queries; // queries.rsを公開
+#[cfg(not(feature = "postgres"))]
 pub mod schema;  // schema.rsを公開
+#[cfg(feature = "postgres")]
+#[path = "pg_schema.rs"]
+pub mod schema;  // postgresフィーチャー有効時はpg_schema.rsをschemaとして公開
+pub mod sql; // sql.rsを公開
+
+/// データベース接続用の型
+/// 通常はMySQL、`postgres` フィーチャー有効時はPostgreSQLへの接続となる。
+#[cfg(not(feature = "postgres"))]
+pub type DbConnection = diesel::MysqlConnection;
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel::PgConnection;
+
+#[cfg(all(test, feature = "postgres"))]
+mod tests {
+    use super::{schema, DbConnection};
+    use crate::models::{Comment, Post, PublicUser, User};
+    use diesel::prelude::*;
+    use diesel::query_dsl::LoadQuery;
+
+    // PostgreSQL用のスキーマとモデルの組み合わせでクエリを実行できることをコンパイル時に確認する
+    fn assert_loadable<'q, T, Q: LoadQuery<'q, DbConnection, T>>(_query: Q) {}
+
+    fn assert_pg_backend<C: Connection<Backend = diesel::pg::Pg>>() {}
+
+    #[test]
+    fn db_connection_uses_pg_backend() {
+        assert_pg_backend::<DbConnection>();
+    }
+
+    #[test]
+    fn models_load_from_pg_schema() {
+        assert_loadable::<User, _>(schema::users::table);
+        assert_loadable::<Post, _>(schema::posts::table.filter(schema::posts::published.eq(true)));
+        assert_loadable::<Comment, _>(schema::comments::table.order(schema::comments::created_at.desc()));
+        assert_loadable::<(Post, PublicUser), _>(
+            schema::posts::table
+                .inner_join(schema::users::table)
+                .select((schema::posts::all_columns, PublicUser::as_select())),
+        );
+    }
+
+    #[test]
+    fn queries_render_pg_sql() {
+        let query = schema::posts::table.filter(schema::posts::post_id.eq(1)).select(schema::posts::title);
+        let sql = diesel::debug_query::<diesel::pg::Pg, _>(&query).to_string();
+        assert!(sql.contains("$1"), "{}", sql);
+    }
+}
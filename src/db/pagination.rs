@@ -0,0 +1,71 @@
+//! ページネーションモジュール
+//!
+//! 一覧取得を行うクエリで共通して使用する `limit` と `offset` の検証処理を提供します。
+//! ページサイズの上限はこのモジュールで一元管理します。
+
+use crate::error::BrogError;
+
+/// 1ページで取得できる最大件数
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// 取得件数を 1 以上 `MAX_PAGE_SIZE` 以下に丸める関数
+///
+/// # 引数
+/// - `requested`: 呼び出し側が指定した取得件数
+///
+/// # 戻り値
+/// - 実際にクエリで使用する取得件数
+pub fn clamp_limit(requested: i64) -> i64 {
+    requested.clamp(1, MAX_PAGE_SIZE)
+}
+
+/// オフセットを検証する関数
+///
+/// # 引数
+/// - `offset`: 呼び出し側が指定したオフセット
+///
+/// # 戻り値
+/// - オフセットが負の値の場合は `BrogError::Validation`
+pub fn validate_offset(offset: i64) -> Result<(), BrogError> {
+    if offset < 0 {
+        return Err(BrogError::Validation(format!("offset must not be negative: {}", offset)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_limit_raises_zero_and_negative_to_one() {
+        assert_eq!(clamp_limit(0), 1);
+        assert_eq!(clamp_limit(-5), 1);
+        assert_eq!(clamp_limit(i64::MIN), 1);
+    }
+
+    #[test]
+    fn clamp_limit_keeps_values_in_range() {
+        assert_eq!(clamp_limit(1), 1);
+        assert_eq!(clamp_limit(20), 20);
+        assert_eq!(clamp_limit(MAX_PAGE_SIZE), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn clamp_limit_lowers_values_over_max() {
+        assert_eq!(clamp_limit(MAX_PAGE_SIZE + 1), MAX_PAGE_SIZE);
+        assert_eq!(clamp_limit(i64::MAX), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn validate_offset_accepts_zero_and_positive() {
+        assert!(validate_offset(0).is_ok());
+        assert!(validate_offset(250).is_ok());
+    }
+
+    #[test]
+    fn validate_offset_rejects_negative() {
+        assert!(matches!(validate_offset(-1), Err(BrogError::Validation(_))));
+        assert!(matches!(validate_offset(i64::MIN), Err(BrogError::Validation(_))));
+    }
+}
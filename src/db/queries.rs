@@ -1,41 +1,63 @@
-/// クエリモジュール
-/// 
-/// このファイルは、Dieselを使用してユーザー、投稿、コメントのCRUD操作を行うための関数を提供します。
-/// データベースはMySQLを使用し、Dieselのクエリビルダを使ってデータベースとのやり取りを行います。
-/// ユーザー、投稿、コメントの作成(create)および削除(delete)操作を行います。
-/// 
-/// 使用するフレームワークとライブラリ：
-/// - crate/models:    自作ライブラリ モデル定義用ファイル
-/// - crate/db/schema: 自作ライブラリ Dieselのスキーマ定義用ファイル(自動生成)
-/// - diesel/prelude : クエリ構築な必要なメソッド、構造体をインポート
-/// - diesel/MyssqlConnection   : MySQL接続用のオブジェクト、preludeをインポートしていれば必要ないが明示的にするため
-/// - diesel/result/QueryResult : クエリ結果を扱うための型、preludeをインポートしていれば必要ないが明示的にするため
-///
+//! クエリモジュール
+//! 
+//! このファイルは、Dieselを使用してユーザー、投稿、コメントのCRUD操作を行うための関数を提供します。
+//! データベースはMySQLを使用し(`postgres` フィーチャー有効時はPostgreSQL)、Dieselのクエリビルダを使ってデータベースとのやり取りを行います。
+//! ユーザー、投稿、コメントの作成(create)および削除(delete)操作を行います。
+//! 
+//! 使用するフレームワークとライブラリ：
+//! - crate/models:    自作ライブラリ モデル定義用ファイル
+//! - crate/db/schema: 自作ライブラリ Dieselのスキーマ定義用ファイル(自動生成)
+//! - diesel/prelude : クエリ構築な必要なメソッド、構造体をインポート
+//! - crate/db/DbConnection    : 自作ライブラリ 使用するバックエンドに応じたデータベース接続用の型
+//! - diesel/result/QueryResult : クエリ結果を扱うための型、preludeをインポートしていれば必要ないが明示的にするため
+//!
 
 use crate::models; 
+use crate::email::Email;
+use crate::error::BrogError;
+use crate::password;
 use crate::db::schema; 
+use crate::db::pagination;
+use crate::db::sql;
+use std::collections::{HashMap, HashSet};
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*; 
-use diesel::MysqlConnection; 
+use crate::db::DbConnection;
 use diesel::result::QueryResult;
+use rand::distributions::{Alphanumeric, DistString};
+use sha2::{Digest, Sha256};
 
 /// 新しいユーザーを作成する関数
 ///
 /// 指定された `name`、`email`、`password_hash` を持つ新しいユーザーをデータベースに挿入します。
 /// 成功した場合、挿入したユーザーを返します。
+/// `unique-usernames` フィーチャー有効時は、同じ名前のユーザーが既に存在する場合に作成しません。
 ///
 /// # 引数
-/// - `conn`: データベース接続用のMysqlConnectionオブジェクト
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
 /// - `name`: ユーザーの名前
 /// - `email`: ユーザーのメールアドレス
 /// - `password_hash`: ユーザーのパスワードハッシュ
 ///
 /// # 戻り値
 /// - 作成された `User` オブジェクト
-pub fn create_user(conn: &mut MysqlConnection, name: &str, email: &str, password_hash: &str) -> QueryResult<models::User> {
+/// - `unique-usernames` フィーチャー有効時に名前が使用済みの場合は `BrogError::DuplicateName`
+pub fn create_user(conn: &mut DbConnection, name: &str, email: &Email, password_hash: &str) -> Result<models::User, BrogError> {
+    #[cfg(feature = "unique-usernames")]
+    {
+        let taken = diesel::select(diesel::dsl::exists(
+            schema::users::table.filter(schema::users::name.eq(name)),
+        ))
+        .get_result::<bool>(conn)?;
+        if taken {
+            return Err(BrogError::DuplicateName);
+        }
+    }
+
     let new_user = models::NewUser {
         name: name.to_string(),
-        email: email.to_string(),
-        password_hash: password_hash.to_string(),
+        email: email.as_str().to_string(),
+        password_hash: password::PasswordHash::new(password_hash.to_string()),
     };
 
     // 新しいユーザーをテーブルに挿入
@@ -44,7 +66,8 @@ pub fn create_user(conn: &mut MysqlConnection, name: &str, email: &str, password
         .execute(conn)?;
 
     // 最後に挿入されたユーザーを取得
-    schema::users::table.order(schema::users::user_id.desc()).first(conn)
+    let user = schema::users::table.order(schema::users::user_id.desc()).first(conn)?;
+    Ok(user)
 }
 
 /// ユーザーを削除する関数
@@ -52,12 +75,12 @@ pub fn create_user(conn: &mut MysqlConnection, name: &str, email: &str, password
 /// 指定された `user_id` を持つユーザーをデータベースから削除します。
 ///
 /// # 引数
-/// - `conn`: データベース接続用のMysqlConnectionオブジェクト
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
 /// - `user_id`: 削除するユーザーのID
 ///
 /// # 戻り値
 /// - 削除された行数
-pub fn delete_user(conn: &mut MysqlConnection, user_id: i32) -> QueryResult<usize> {
+pub fn delete_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<usize> {
     let affected_rows = diesel::delete(schema::users::table.find(user_id)).execute(conn)?;
     if affected_rows == 0 {
         println!("No user with id {} found", user_id);
@@ -65,12 +88,303 @@ pub fn delete_user(conn: &mut MysqlConnection, user_id: i32) -> QueryResult<usiz
     Ok(affected_rows)
 }
 
+/// ユーザーを無効化する関数
+///
+/// アカウントを削除する代わりに無効にします。
+/// 無効化されたユーザーの投稿は公開の一覧に表示されなくなりますが、データは残るため `reactivate_user` で元に戻せます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 無効化するユーザーのID
+///
+/// # 戻り値
+/// - 更新された行数
+pub fn deactivate_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<usize> {
+    diesel::update(schema::users::table.find(user_id))
+        .set(schema::users::active.eq(false))
+        .execute(conn)
+}
+
+/// 無効化したユーザーを元に戻す関数
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 元に戻すユーザーのID
+///
+/// # 戻り値
+/// - 更新された行数
+pub fn reactivate_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<usize> {
+    diesel::update(schema::users::table.find(user_id))
+        .set(schema::users::active.eq(true))
+        .execute(conn)
+}
+
+/// 有効なユーザーのIDを取得するサブクエリの型
+type ActiveUserIds = diesel::dsl::Select<
+    diesel::dsl::Filter<schema::users::table, diesel::dsl::Eq<schema::users::active, bool>>,
+    schema::users::user_id,
+>;
+
+/// 有効なユーザーのIDを取得するサブクエリ
+///
+/// 公開の一覧から無効化されたユーザーの投稿を除くため、`posts::user_id.eq_any(active_user_ids())` のように使用します。
+fn active_user_ids() -> ActiveUserIds {
+    schema::users::table
+        .filter(schema::users::active.eq(true))
+        .select(schema::users::user_id)
+}
+
+/// 現在日時(データベースの時刻)を表すSQL式の型
+type PublishAtNow = diesel::expression::SqlLiteral<diesel::dsl::SqlTypeOf<schema::posts::publish_at>>;
+
+/// 公開日時に達しているかを判定する式の型
+type PublishTimeReached = diesel::dsl::Or<
+    diesel::dsl::IsNull<schema::posts::publish_at>,
+    diesel::dsl::LtEq<schema::posts::publish_at, PublishAtNow>,
+>;
+
+/// 投稿の公開日時に達しているかを判定する式
+///
+/// 予約されていない(`publish_at` が `NULL`)か、予約した日時を過ぎている場合に真となります。
+/// 公開の一覧で `.filter(publish_time_reached())` のように使用します。
+fn publish_time_reached() -> PublishTimeReached {
+    schema::posts::publish_at.is_null().or(schema::posts::publish_at.le(publish_at_now()))
+}
+
+/// `publish_at` と比較するための現在日時(データベースの時刻)の式
+///
+/// 別名を付けた `posts` で公開日時を判定する場合など、`publish_time_reached` を使えない場合に使用します。
+fn publish_at_now() -> PublishAtNow {
+    diesel::dsl::sql::<diesel::dsl::SqlTypeOf<schema::posts::publish_at>>("CURRENT_TIMESTAMP(6)")
+}
+
+/// IDでユーザーを取得する関数
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 取得するユーザーのID
+///
+/// # 戻り値
+/// - 見つかった `User` オブジェクト
+/// - ユーザーが存在しない場合は `NotFound` エラー
+pub fn get_user_by_id(conn: &mut DbConnection, user_id: i32) -> QueryResult<models::User> {
+    schema::users::table.find(user_id).first(conn)
+}
+
+/// IDでユーザーを検索する関数
+///
+/// `get_user_by_id` と異なり、ユーザーが存在しないことをエラーではなく `None` として返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 検索するユーザーのID
+///
+/// # 戻り値
+/// - 見つかった場合は `Some(User)`、存在しない場合は `None`
+pub fn find_user_by_id(conn: &mut DbConnection, user_id: i32) -> QueryResult<Option<models::User>> {
+    schema::users::table.find(user_id).first(conn).optional()
+}
+
+/// メールアドレスでユーザーを取得する関数
+///
+/// メールアドレスは大文字・小文字を区別せずに比較します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `email`: 検索するメールアドレス
+///
+/// # 戻り値
+/// - 見つかった場合は `Some(User)`、存在しない場合は `None`
+pub fn get_user_by_email(conn: &mut DbConnection, email: &Email) -> QueryResult<Option<models::User>> {
+    schema::users::table
+        .filter(sql::lower(schema::users::email).eq(email.as_str()))
+        .order(schema::users::user_id.asc())
+        .first(conn)
+        .optional()
+}
+
+/// メールアドレスで有効なユーザーを取得する関数
+///
+/// ログインなど、無効化されたユーザーを対象外にしたい場合に使用します。
+/// メールアドレスは大文字・小文字を区別せずに比較します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `email`: 検索するメールアドレス
+///
+/// # 戻り値
+/// - 見つかった場合は `Some(User)`、存在しないか無効化されている場合は `None`
+pub fn get_active_user_by_email(conn: &mut DbConnection, email: &Email) -> QueryResult<Option<models::User>> {
+    schema::users::table
+        .filter(sql::lower(schema::users::email).eq(email.as_str()))
+        .filter(schema::users::active.eq(true))
+        .order(schema::users::user_id.asc())
+        .first(conn)
+        .optional()
+}
+
+/// 名前でユーザーを取得する関数
+///
+/// 指定された `name` を持つユーザーを取得します。
+/// ユーザー名は一意ではないため、同名のユーザーが複数いる場合は `user_id` が最も小さいユーザーを返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `name`: 検索するユーザーの名前
+///
+/// # 戻り値
+/// - 見つかった場合は `Some(User)`、存在しない場合は `None`
+pub fn get_user_by_name(conn: &mut DbConnection, name: &str) -> QueryResult<Option<models::User>> {
+    schema::users::table
+        .filter(schema::users::name.eq(name))
+        .order(schema::users::user_id.asc())
+        .first(conn)
+        .optional()
+}
+
+/// 重複しているメールアドレスを取得する関数
+///
+/// 複数のユーザーで使用されているメールアドレスと、その使用件数を取得します。
+/// 重複データを整理するマイグレーションの事前確認に使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - メールアドレスと件数の組のリスト
+pub fn find_duplicate_emails(conn: &mut DbConnection) -> QueryResult<Vec<(String, i64)>> {
+    schema::users::table
+        .group_by(schema::users::email)
+        .select((schema::users::email, diesel::dsl::count_star()))
+        .having(diesel::dsl::count_star().gt(1))
+        .order(schema::users::email.asc())
+        .load(conn)
+}
+
+/// 登録時に作成する歓迎投稿のタイトル
+const WELCOME_POST_TITLE: &str = "Welcome to BrogApp!";
+
+/// 登録時に作成する歓迎投稿の本文
+const WELCOME_POST_BODY: &str = "This is your first post. Edit or delete it, then start writing!";
+
+/// ユーザー登録を行う関数
+///
+/// パスワードをハッシュ化してユーザーを作成し、同時に歓迎投稿を作成します。
+/// ユーザーと投稿の作成は1つのトランザクションで行い、投稿の作成に失敗した場合はユーザーの作成も取り消します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `name`: ユーザーの名前
+/// - `email`: ユーザーのメールアドレス
+/// - `password`: 平文のパスワード
+///
+/// # 戻り値
+/// - 作成された `User` オブジェクトと歓迎投稿の `Post` オブジェクト
+pub fn register_user(conn: &mut DbConnection, name: &str, email: &Email, password: &str) -> Result<(models::User, models::Post), BrogError> {
+    let password_hash = password::hash_password(password)?;
+
+    conn.transaction(|conn| {
+        let user = create_user(conn, name, email, password_hash.as_str())?;
+        let post = create_post(conn, WELCOME_POST_TITLE, WELCOME_POST_BODY, true, user.user_id())?;
+        Ok((user, post))
+    })
+}
+
+/// パスワードリセットトークンの文字数
+const PASSWORD_RESET_TOKEN_LENGTH: usize = 64;
+
+/// パスワードリセットトークンの有効期間(分)
+const PASSWORD_RESET_EXPIRY_MINUTES: i64 = 60;
+
+/// パスワードリセットを要求する関数
+///
+/// 指定された `email` のユーザーに対してパスワードリセット用のトークンを発行します。
+/// メールアドレスは `get_user_by_email` と同じく大文字・小文字を区別せずに照合します。
+/// データベースにはトークンのハッシュのみを保存するため、発行したトークンは戻り値でしか取得できません。
+/// トークンの有効期限は発行から `PASSWORD_RESET_EXPIRY_MINUTES` 分です。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `email`: パスワードをリセットするユーザーのメールアドレス
+///
+/// # 戻り値
+/// - 発行したトークン
+/// - ユーザーが存在しない場合は `NotFound` エラー
+pub fn request_password_reset(conn: &mut DbConnection, email: &Email) -> QueryResult<String> {
+    let user = get_user_by_email(conn, email)?.ok_or(diesel::result::Error::NotFound)?;
+
+    let token = Alphanumeric.sample_string(&mut rand::thread_rng(), PASSWORD_RESET_TOKEN_LENGTH);
+    let new_reset = models::NewPasswordReset {
+        token: hash_reset_token(&token),
+        user_id: user.user_id(),
+        expires_at: chrono::Utc::now().naive_utc() + chrono::Duration::minutes(PASSWORD_RESET_EXPIRY_MINUTES),
+    };
+
+    diesel::insert_into(schema::password_resets::table)
+        .values(&new_reset)
+        .execute(conn)?;
+
+    Ok(token)
+}
+
+/// パスワードリセットトークンをハッシュ化する関数
+///
+/// トークンは十分に長い乱数のため、ソルトなしのSHA-256で保存します。
+/// 照合時も同じ関数でハッシュ化した値で検索します。
+fn hash_reset_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// パスワードをリセットする関数
+///
+/// トークンが有効期限内かつ未使用であることを確認し、ユーザーのパスワードハッシュを更新してトークンを使用済みにします。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `token`: `request_password_reset` で発行したトークン
+/// - `new_plaintext`: 新しい平文のパスワード
+///
+/// # 戻り値
+/// - トークンが存在しない場合は `BrogError::NotFound`
+/// - トークンが期限切れまたは使用済みの場合は `BrogError::Validation`
+pub fn reset_password(conn: &mut DbConnection, token: &str, new_plaintext: &str) -> Result<(), BrogError> {
+    let password_hash = password::hash_password(new_plaintext)?;
+    let token_hash = hash_reset_token(token);
+
+    conn.transaction(|conn| {
+        let reset = schema::password_resets::table
+            .find(&token_hash)
+            .for_update()
+            .first::<models::PasswordReset>(conn)?;
+
+        if reset.used() {
+            return Err(BrogError::Validation("password reset token has already been used".to_string()));
+        }
+        if reset.expires_at() <= chrono::Utc::now().naive_utc() {
+            return Err(BrogError::Validation("password reset token has expired".to_string()));
+        }
+
+        diesel::update(schema::users::table.find(reset.user_id()))
+            .set(schema::users::password_hash.eq(&password_hash))
+            .execute(conn)?;
+
+        diesel::update(schema::password_resets::table.find(&token_hash))
+            .set(schema::password_resets::used.eq(true))
+            .execute(conn)?;
+
+        Ok(())
+    })
+}
+
 /// 新しい投稿を作成する関数
 ///
 /// ユーザーが作成する投稿をデータベースに挿入します。投稿にはタイトル、本文、公開ステータスが含まれます。
 ///
 /// # 引数
-/// - `conn`: データベース接続用のMysqlConnectionオブジェクト
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
 /// - `title`: 投稿のタイトル
 /// - `body`: 投稿の本文
 /// - `is_published`: 投稿が公開されているかどうか
@@ -78,7 +392,12 @@ pub fn delete_user(conn: &mut MysqlConnection, user_id: i32) -> QueryResult<usiz
 ///
 /// # 戻り値
 /// - 作成された `Post` オブジェクト
-pub fn create_post(conn: &mut MysqlConnection, title: &str, body: &str, is_published: bool, user_id: i32) -> QueryResult<models::Post> {
+/// - 公開する場合にタイトルまたは本文が空のときは `BrogError::Validation`
+pub fn create_post(conn: &mut DbConnection, title: &str, body: &str, is_published: bool, user_id: i32) -> Result<models::Post, BrogError> {
+    if is_published {
+        models::validate_publishable(title, body)?;
+    }
+
     let new_post = models::NewPost {
         title: title.to_string(),
         post_body: body.to_string(),
@@ -86,13 +405,169 @@ pub fn create_post(conn: &mut MysqlConnection, title: &str, body: &str, is_publi
         user_id,
     };
 
-    // 新しい投稿をテーブルに挿入
-    diesel::insert_into(schema::posts::table)
-        .values(&new_post)
+    conn.transaction(|conn| {
+        // 新しい投稿をテーブルに挿入
+        diesel::insert_into(schema::posts::table)
+            .values(&new_post)
+            .execute(conn)?;
+
+        // 最後に挿入された投稿のIDを取得
+        let post_id = schema::posts::table
+            .select(schema::posts::post_id)
+            .order(schema::posts::post_id.desc())
+            .first::<i32>(conn)?;
+
+        // IDを含めることで一意になるスラッグと、作成順となる表示順を設定
+        diesel::update(schema::posts::table.find(post_id))
+            .set((
+                schema::posts::slug.eq(build_slug(title, post_id)),
+                schema::posts::sort_order.eq(post_id),
+            ))
+            .execute(conn)?;
+        record_post_revision(conn, post_id, body)?;
+
+        let post = schema::posts::table.find(post_id).first(conn)?;
+        Ok(post)
+    })
+}
+
+/// スラッグに含めるタイトル部分の最大文字数
+const SLUG_TITLE_MAX_CHARS: usize = 200;
+
+/// 投稿のスラッグを作成する関数
+///
+/// タイトルの英数字を小文字にし、それ以外の文字を `-` に置き換えたものに投稿IDを付けて作成します。
+/// 日本語のタイトルなど英数字を含まない場合は `post-{post_id}` となります。
+fn build_slug(title: &str, post_id: i32) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= SLUG_TITLE_MAX_CHARS {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        format!("post-{}", post_id)
+    } else {
+        format!("{}-{}", slug, post_id)
+    }
+}
+
+/// 投稿を更新する関数
+///
+/// 指定された `post_id` の投稿のタイトル、本文、公開ステータスを書き換え、バージョン番号を1増やします。
+/// 本文が変わった場合は変更後の本文を版として記録します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 更新する投稿のID
+/// - `title`: 新しいタイトル
+/// - `body`: 新しい本文
+/// - `is_published`: 投稿を公開するかどうか
+///
+/// # 戻り値
+/// - 更新後の `Post` オブジェクト
+/// - 投稿が存在しない場合は `BrogError::NotFound`
+/// - 公開する場合にタイトルまたは本文が空のときは `BrogError::Validation`
+pub fn update_post(conn: &mut DbConnection, post_id: i32, title: &str, body: &str, is_published: bool) -> Result<models::Post, BrogError> {
+    if is_published {
+        models::validate_publishable(title, body)?;
+    }
+
+    conn.transaction(|conn| {
+        diesel::update(schema::posts::table.find(post_id))
+            .set((
+                schema::posts::title.eq(title),
+                schema::posts::post_body.eq(body),
+                schema::posts::published.eq(is_published),
+                schema::posts::version.eq(schema::posts::version + 1),
+            ))
+            .execute(conn)?;
+
+        let post = schema::posts::table.find(post_id).first(conn)?;
+        record_post_revision(conn, post_id, body)?;
+        Ok(post)
+    })
+}
+
+/// バージョンを確認して投稿を更新する関数
+///
+/// 投稿の `version` が `expected_version` と一致する場合のみ更新します。
+/// 編集を始めた後に他の編集者が投稿を更新していた場合は、上書きせずに `BrogError::Conflict` を返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 更新する投稿のID
+/// - `expected_version`: 編集を開始した時点での投稿の `version`
+/// - `title`: 新しいタイトル
+/// - `body`: 新しい本文
+/// - `is_published`: 投稿を公開するかどうか
+///
+/// # 戻り値
+/// - 更新後の `Post` オブジェクト
+/// - 投稿が存在しない場合は `BrogError::NotFound`
+/// - バージョンが一致しない場合は `BrogError::Conflict`
+/// - 公開する場合にタイトルまたは本文が空のときは `BrogError::Validation`
+pub fn update_post_versioned(conn: &mut DbConnection, post_id: i32, expected_version: i32, title: &str, body: &str, is_published: bool) -> Result<models::Post, BrogError> {
+    if is_published {
+        models::validate_publishable(title, body)?;
+    }
+
+    conn.transaction(|conn| {
+        let affected_rows = diesel::update(
+            schema::posts::table
+                .find(post_id)
+                .filter(schema::posts::version.eq(expected_version)),
+        )
+        .set((
+            schema::posts::title.eq(title),
+            schema::posts::post_body.eq(body),
+            schema::posts::published.eq(is_published),
+            schema::posts::version.eq(schema::posts::version + 1),
+        ))
         .execute(conn)?;
 
-    // 最後に挿入された投稿を取得
-    schema::posts::table.order(schema::posts::post_id.desc()).first(conn)
+        if affected_rows == 0 {
+            // 投稿が存在しなければ NotFound、存在すればバージョンの不一致
+            schema::posts::table.find(post_id).select(schema::posts::post_id).first::<i32>(conn)?;
+            return Err(BrogError::Conflict);
+        }
+        record_post_revision(conn, post_id, body)?;
+
+        let post = schema::posts::table.find(post_id).first(conn)?;
+        Ok(post)
+    })
+}
+
+/// 投稿の本文を版として記録する関数
+///
+/// 最新の版と本文が同じ場合(タイトルや公開ステータスのみの変更など)は記録しません。
+/// 投稿の作成・更新と同じトランザクション内で呼び出してください。
+fn record_post_revision(conn: &mut DbConnection, post_id: i32, body: &str) -> QueryResult<()> {
+    let latest_body = schema::post_revisions::table
+        .filter(schema::post_revisions::post_id.eq(post_id))
+        .select(schema::post_revisions::post_body)
+        .order(schema::post_revisions::revision_id.desc())
+        .first::<String>(conn)
+        .optional()?;
+    if latest_body.as_deref() == Some(body) {
+        return Ok(());
+    }
+
+    let new_revision = models::NewPostRevision {
+        post_id,
+        post_body: body.to_string(),
+    };
+    diesel::insert_into(schema::post_revisions::table)
+        .values(&new_revision)
+        .execute(conn)?;
+    Ok(())
 }
 
 /// 投稿を削除する関数
@@ -100,12 +575,12 @@ pub fn create_post(conn: &mut MysqlConnection, title: &str, body: &str, is_publi
 /// 指定された `post_id` を持つ投稿をデータベースから削除します。
 ///
 /// # 引数
-/// - `conn`: データベース接続用のMysqlConnectionオブジェクト
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
 /// - `post_id`: 削除する投稿のID
 ///
 /// # 戻り値
 /// - 削除された行数
-pub fn delete_post(conn: &mut MysqlConnection, post_id: i32) -> QueryResult<usize> {
+pub fn delete_post(conn: &mut DbConnection, post_id: i32) -> QueryResult<usize> {
     let affected_rows = diesel::delete(schema::posts::table.find(post_id)).execute(conn)?;
     if affected_rows == 0 {
         println!("No post with id {} found", post_id);
@@ -113,32 +588,335 @@ pub fn delete_post(conn: &mut MysqlConnection, post_id: i32) -> QueryResult<usiz
     Ok(affected_rows)
 }
 
+/// 投稿を参照している行を削除する関数
+///
+/// 指定された投稿に付いたコメント(返信とメンションを含む)、いいね、タグ付けを削除します。
+/// 外部キー制約に `ON DELETE CASCADE` がないため、投稿を削除する前にトランザクション内で呼び出します。
+/// 投稿の版は `ON DELETE CASCADE` で投稿と一緒に削除されます。
+fn delete_post_dependents(conn: &mut DbConnection, post_ids: &[i32]) -> QueryResult<()> {
+    // 返信は親コメントと同じ投稿に付くため、投稿内の参照を外してからまとめて削除する
+    diesel::update(schema::comments::table.filter(schema::comments::post_id.eq_any(post_ids)))
+        .set(schema::comments::parent_comment_id.eq(None::<i32>))
+        .execute(conn)?;
+    diesel::delete(schema::comments::table.filter(schema::comments::post_id.eq_any(post_ids))).execute(conn)?;
+    diesel::delete(schema::post_likes::table.filter(schema::post_likes::post_id.eq_any(post_ids))).execute(conn)?;
+    diesel::delete(schema::post_tags::table.filter(schema::post_tags::post_id.eq_any(post_ids))).execute(conn)?;
+    Ok(())
+}
+
 /// 新しいコメントを作成する関数
 ///
 /// 指定された `user_id`、`post_id`、`body` を持つコメントをデータベースに挿入します。
+/// 本文中の `@ユーザー名` はメンションとして同じトランザクション内で記録します。
+/// 本文が `looks_like_spam` に該当する場合は `flagged` を付けて挿入します。
 ///
 /// # 引数
-/// - `conn`: データベース接続用のMysqlConnectionオブジェクト
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
 /// - `user_id`: コメントを作成したユーザーのID
 /// - `post_id`: コメントが関連する投稿のID
 /// - `body`: コメントの本文
 ///
 /// # 戻り値
 /// - 作成された `Comment` オブジェクト
-pub fn create_comment(conn: &mut MysqlConnection, user_id: i32, post_id: i32, body: &str) -> QueryResult<models::Comment> {
+/// - 本文が `MAX_COMMENT_CHARS` 文字を超える場合は `BrogError::Validation`
+/// - 投稿が存在しない場合は `BrogError::NotFound`
+/// - 投稿がコメントを受け付けていない場合は `BrogError::CommentsClosed`
+pub fn create_comment(conn: &mut DbConnection, user_id: i32, post_id: i32, body: &str) -> Result<models::Comment, BrogError> {
+    validate_comment_body(body)?;
+
     let new_comment = models::NewComment {
         user_id,
         post_id,
-        comment_body: body.to_string(), 
+        comment_body: body.to_string(),
+        parent_comment_id: None,
+        flagged: looks_like_spam(body),
     };
 
-    // 新しいコメントをテーブルに挿入
-    diesel::insert_into(schema::comments::table)
-        .values(&new_comment)
+    conn.transaction(|conn| {
+        ensure_comments_open(conn, post_id)?;
+
+        // 新しいコメントをテーブルに挿入
+        diesel::insert_into(schema::comments::table)
+            .values(&new_comment)
+            .execute(conn)?;
+
+        // 最後に挿入されたコメントを取得
+        let comment = schema::comments::table.order(schema::comments::comment_id.desc()).first::<models::Comment>(conn)?;
+        record_mentions(conn, comment.comment_id(), body)?;
+        Ok(comment)
+    })
+}
+
+/// 投稿がコメントを受け付けているかを確認する関数
+///
+/// 投稿が存在しない場合は `BrogError::NotFound`、コメントを受け付けていない場合は `BrogError::CommentsClosed` を返します。
+fn ensure_comments_open(conn: &mut DbConnection, post_id: i32) -> Result<(), BrogError> {
+    let comments_enabled = schema::posts::table
+        .find(post_id)
+        .select(schema::posts::comments_enabled)
+        .first::<bool>(conn)?;
+    if !comments_enabled {
+        return Err(BrogError::CommentsClosed);
+    }
+    Ok(())
+}
+
+/// コメントへの返信を作成する関数
+///
+/// 指定された `parent_comment_id` のコメントへの返信を作成します。
+/// 返信は返信先のコメントと同じ投稿に関連付けられます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 返信を作成したユーザーのID
+/// - `parent_comment_id`: 返信先のコメントのID
+/// - `body`: 返信の本文
+///
+/// # 戻り値
+/// - 作成された `Comment` オブジェクト
+/// - 返信先のコメントが存在しない場合は `BrogError::NotFound`
+/// - 本文が `MAX_COMMENT_CHARS` 文字を超える場合は `BrogError::Validation`
+/// - 投稿がコメントを受け付けていない場合は `BrogError::CommentsClosed`
+pub fn create_reply(conn: &mut DbConnection, user_id: i32, parent_comment_id: i32, body: &str) -> Result<models::Comment, BrogError> {
+    validate_comment_body(body)?;
+
+    conn.transaction(|conn| {
+        let post_id = schema::comments::table
+            .find(parent_comment_id)
+            .select(schema::comments::post_id)
+            .first::<i32>(conn)?;
+        ensure_comments_open(conn, post_id)?;
+
+        let new_comment = models::NewComment {
+            user_id,
+            post_id,
+            comment_body: body.to_string(),
+            parent_comment_id: Some(parent_comment_id),
+            flagged: looks_like_spam(body),
+        };
+
+        // 新しい返信をテーブルに挿入
+        diesel::insert_into(schema::comments::table)
+            .values(&new_comment)
+            .execute(conn)?;
+
+        // 最後に挿入された返信を取得
+        let comment = schema::comments::table.order(schema::comments::comment_id.desc()).first::<models::Comment>(conn)?;
+        record_mentions(conn, comment.comment_id(), body)?;
+        Ok(comment)
+    })
+}
+
+/// スパムとみなすリンクの数
+const SPAM_MIN_LINKS: usize = 4;
+
+/// スパムとみなす同じ文字の連続数
+const SPAM_MIN_REPEATED_CHARS: usize = 10;
+
+/// 大文字の割合を判定する際に必要な英字の数
+/// 短い略語(「OK」など)だけのコメントをスパムとみなさないようにする。
+const SPAM_CAPS_MIN_LETTERS: usize = 20;
+
+/// スパムとみなす英字に占める大文字の割合
+const SPAM_CAPS_RATIO: f64 = 0.7;
+
+/// コメント本文がスパムらしいかを判定する関数
+///
+/// 次のいずれかに該当する場合にスパムらしいと判定します。
+/// - リンク(`http://`、`https://`、`www.`)が `SPAM_MIN_LINKS` 個以上ある(`https://www.` は1個と数える)
+/// - 空白以外の同じ文字が `SPAM_MIN_REPEATED_CHARS` 文字以上連続している
+/// - 英字が `SPAM_CAPS_MIN_LETTERS` 文字以上あり、そのうち大文字の割合が `SPAM_CAPS_RATIO` を超える
+///
+/// 判定は目安のため、該当したコメントも削除はせずフラグを付けるだけにしてください。
+pub fn looks_like_spam(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    // `https://www.` のようにスキームの直後に続く `www.` は同じリンクとして数えない
+    let bare_www = lower.match_indices("www.").filter(|(index, _)| !lower[..*index].ends_with("://")).count();
+    let links = lower.matches("http://").count() + lower.matches("https://").count() + bare_www;
+    if links >= SPAM_MIN_LINKS {
+        return true;
+    }
+
+    let mut run = 0;
+    let mut previous = None;
+    for c in body.chars() {
+        run = if Some(c) == previous { run + 1 } else { 1 };
+        previous = Some(c);
+        if !c.is_whitespace() && run >= SPAM_MIN_REPEATED_CHARS {
+            return true;
+        }
+    }
+
+    let letters = body.chars().filter(|c| c.is_ascii_alphabetic()).count();
+    let uppercase = body.chars().filter(|c| c.is_ascii_uppercase()).count();
+    letters >= SPAM_CAPS_MIN_LETTERS && uppercase as f64 / letters as f64 > SPAM_CAPS_RATIO
+}
+
+/// コメント本文からメンションされたユーザー名を取り出す関数
+///
+/// `@` に続く英数字・`_`・`-` をユーザー名として取り出します。
+/// メールアドレスのように `@` の直前がユーザー名に使える文字の場合はメンションとみなしません。
+/// 同じユーザー名は最初の1つだけを返します。
+pub fn extract_mentions(body: &str) -> Vec<String> {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+
+    let mut names: Vec<String> = Vec::new();
+    let mut previous = None;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '@' && !previous.is_some_and(is_name_char) {
+            let mut name = String::new();
+            while let Some(&next) = chars.peek() {
+                if !is_name_char(next) {
+                    break;
+                }
+                name.push(next);
+                chars.next();
+            }
+            previous = name.chars().last().or(Some(c));
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+            continue;
+        }
+        previous = Some(c);
+    }
+    names
+}
+
+/// コメントのメンションを記録する関数
+///
+/// 本文中のユーザー名を `users` の名前と照合し、見つかったユーザーへのメンションを挿入します。
+/// 存在しないユーザー名は無視します。同名のユーザーが複数いる場合は `user_id` が最も小さいユーザーとします。
+fn record_mentions(conn: &mut DbConnection, comment_id: i32, body: &str) -> QueryResult<usize> {
+    let names = extract_mentions(body);
+    if names.is_empty() {
+        return Ok(0);
+    }
+
+    let users = schema::users::table
+        .filter(schema::users::name.eq_any(&names))
+        .select((schema::users::user_id, schema::users::name))
+        .order(schema::users::user_id.asc())
+        .load::<(i32, String)>(conn)?;
+
+    let mut mentioned = HashSet::new();
+    let mut seen_names = HashSet::new();
+    let mentions: Vec<models::NewCommentMention> = users
+        .into_iter()
+        .filter(|(user_id, name)| seen_names.insert(name.clone()) && mentioned.insert(*user_id))
+        .map(|(mentioned_user_id, _)| models::NewCommentMention { comment_id, mentioned_user_id })
+        .collect();
+    if mentions.is_empty() {
+        return Ok(0);
+    }
+
+    diesel::insert_into(schema::comment_mentions::table)
+        .values(&mentions)
+        .execute(conn)
+}
+
+/// コメント本文の最大文字数
+pub const MAX_COMMENT_CHARS: usize = 5000;
+
+/// コメント本文を検証する関数
+///
+/// 本文が `MAX_COMMENT_CHARS` 文字を超える場合は `BrogError::Validation` を返します。
+/// 文字数はバイト数ではなく文字単位で数えます。
+pub(crate) fn validate_comment_body(body: &str) -> Result<(), BrogError> {
+    let length = body.chars().count();
+    if length > MAX_COMMENT_CHARS {
+        return Err(BrogError::Validation(format!(
+            "comment must be at most {} characters (got {})",
+            MAX_COMMENT_CHARS, length
+        )));
+    }
+    Ok(())
+}
+
+/// コメントを更新する関数
+///
+/// 指定された `comment_id` のコメント本文を書き換えます。
+/// 本文が実際に変わった場合は `edited` を `true` にし、`edit_count` を1増やして `updated_at` を更新します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 更新するコメントのID
+/// - `new_body`: 新しいコメント本文
+///
+/// # 戻り値
+/// - 更新後の `Comment` オブジェクト
+/// - コメントが存在しない場合は `BrogError::NotFound`
+/// - 本文が `MAX_COMMENT_CHARS` 文字を超える場合は `BrogError::Validation`
+pub fn update_comment(conn: &mut DbConnection, comment_id: i32, new_body: &str) -> Result<models::Comment, BrogError> {
+    validate_comment_body(new_body)?;
+
+    conn.transaction(|conn| {
+        let comment = schema::comments::table
+            .find(comment_id)
+            .for_update()
+            .first::<models::Comment>(conn)?;
+        apply_comment_edit(conn, comment, new_body)
+    })
+}
+
+/// 楽観的排他制御付きでコメントを更新する関数
+///
+/// コメントの `updated_at` が `expected_updated_at` と一致する場合のみ本文を書き換えます。
+/// 読み込んだ後に他の編集者がコメントを更新していた場合は、上書きせずに `BrogError::Conflict` を返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 更新するコメントのID
+/// - `new_body`: 新しいコメント本文
+/// - `expected_updated_at`: 編集を開始した時点でのコメントの `updated_at`
+///
+/// # 戻り値
+/// - 更新後の `Comment` オブジェクト
+/// - コメントが存在しない場合は `BrogError::NotFound`
+/// - 他の編集者が先に更新していた場合は `BrogError::Conflict`
+/// - 本文が `MAX_COMMENT_CHARS` 文字を超える場合は `BrogError::Validation`
+pub fn update_comment_checked(conn: &mut DbConnection, comment_id: i32, new_body: &str, expected_updated_at: NaiveDateTime) -> Result<models::Comment, BrogError> {
+    validate_comment_body(new_body)?;
+
+    conn.transaction(|conn| {
+        let comment = schema::comments::table
+            .find(comment_id)
+            .for_update()
+            .first::<models::Comment>(conn)
+            .optional()?
+            .ok_or(BrogError::NotFound)?;
+
+        // updated_at が変わっている場合は他の編集者が先に更新している
+        if comment.updated_at() != expected_updated_at {
+            return Err(BrogError::Conflict);
+        }
+        apply_comment_edit(conn, comment, new_body)
+    })
+}
+
+/// 読み込んだコメントに編集を反映する関数
+///
+/// 本文が変わらない場合は何も更新しません。
+/// 更新する値はすべて読み込んだコメントから計算するため、`SET` の代入の評価順に依存しません。
+/// `updated_at` もデータベースの自動更新(MySQLの `ON UPDATE`)に頼らず明示的に設定します。
+/// 行ロックを取得したトランザクション内で呼び出します。
+fn apply_comment_edit(conn: &mut DbConnection, comment: models::Comment, new_body: &str) -> Result<models::Comment, BrogError> {
+    if comment.comment_body() == new_body {
+        return Ok(comment);
+    }
+
+    diesel::update(schema::comments::table.find(comment.comment_id()))
+        .set((
+            schema::comments::comment_body.eq(new_body),
+            schema::comments::edited.eq(true),
+            schema::comments::edit_count.eq(comment.edit_count() + 1),
+            schema::comments::updated_at.eq(diesel::dsl::sql::<diesel::dsl::SqlTypeOf<schema::comments::updated_at>>("CURRENT_TIMESTAMP(6)")),
+        ))
         .execute(conn)?;
 
-    // 最後に挿入されたコメントを取得
-    schema::comments::table.order(schema::comments::comment_id.desc()).first(conn)
+    let comment = schema::comments::table.find(comment.comment_id()).first(conn)?;
+    Ok(comment)
 }
 
 /// コメントを削除する関数
@@ -146,15 +924,2622 @@ pub fn create_comment(conn: &mut MysqlConnection, user_id: i32, post_id: i32, bo
 /// 指定された `id` を持つコメントをデータベースから削除します。
 ///
 /// # 引数
-/// - `conn`: データベース接続用のMysqlConnectionオブジェクト
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
 /// - `id`: 削除するコメントのID
 ///
 /// # 戻り値
 /// - 削除された行数
-pub fn delete_comment(conn: &mut MysqlConnection, id: i32) -> QueryResult<usize> {
+pub fn delete_comment(conn: &mut DbConnection, id: i32) -> QueryResult<usize> {
     let affected_rows = diesel::delete(schema::comments::table.find(id)).execute(conn)?;
     if affected_rows == 0 {
         println!("No comment with id {} found", id);
     }
     Ok(affected_rows)
 }
+
+/// コメントを削除し、削除したコメントを返す関数
+///
+/// 「元に戻す」の表示などで削除した内容を使えるよう、削除する前のコメントを読み込んで返します。
+/// 読み込みと削除は1つのトランザクションで行います。
+/// コメントのメンションは外部キーの `ON DELETE CASCADE` により一緒に削除されます。
+/// 返信は `delete_comment_as` と同様に親を外してから削除するため、返信の付いたコメントも削除できます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 削除するコメントのID
+///
+/// # 戻り値
+/// - 削除した場合は `Some(Comment)`、存在しない場合は `None`
+pub fn delete_comment_returning(conn: &mut DbConnection, comment_id: i32) -> QueryResult<Option<models::Comment>> {
+    conn.transaction(|conn| {
+        let comment = schema::comments::table
+            .find(comment_id)
+            .for_update()
+            .first::<models::Comment>(conn)
+            .optional()?;
+        if comment.is_some() {
+            detach_replies(conn, comment_id)?;
+            diesel::delete(schema::comments::table.find(comment_id)).execute(conn)?;
+        }
+        Ok(comment)
+    })
+}
+
+/// ユーザーをフォローする関数
+///
+/// `follower_id` のユーザーが `followee_id` のユーザーをフォローします。
+/// 自分自身へのフォローおよび既にフォロー済みのユーザーへのフォローは拒否します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `follower_id`: フォローするユーザーのID
+/// - `followee_id`: フォローされるユーザーのID
+///
+/// # 戻り値
+/// - 作成された `Follow` オブジェクト
+/// - 自分自身をフォローしようとした場合は `BrogError::Validation`
+/// - 既にフォロー済みの場合は `BrogError::Conflict`
+pub fn follow_user(conn: &mut DbConnection, follower_id: i32, followee_id: i32) -> Result<models::Follow, BrogError> {
+    if follower_id == followee_id {
+        return Err(BrogError::Validation("users cannot follow themselves".to_string()));
+    }
+
+    conn.transaction(|conn| {
+        // 既にフォロー済みかを確認
+        let already_following = schema::follows::table
+            .filter(schema::follows::follower_id.eq(follower_id))
+            .filter(schema::follows::followee_id.eq(followee_id))
+            .first::<models::Follow>(conn)
+            .optional()?;
+        if already_following.is_some() {
+            return Err(BrogError::Conflict);
+        }
+
+        let new_follow = models::NewFollow {
+            follower_id,
+            followee_id,
+        };
+
+        // 新しいフォローをテーブルに挿入
+        diesel::insert_into(schema::follows::table)
+            .values(&new_follow)
+            .execute(conn)?;
+
+        // 最後に挿入されたフォローを取得
+        let follow = schema::follows::table.order(schema::follows::follow_id.desc()).first(conn)?;
+        Ok(follow)
+    })
+}
+
+/// フォローを解除する関数
+///
+/// `follower_id` のユーザーによる `followee_id` のユーザーへのフォローを削除します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `follower_id`: フォローしているユーザーのID
+/// - `followee_id`: フォローされているユーザーのID
+///
+/// # 戻り値
+/// - 削除された行数(フォローしていなかった場合は 0)
+pub fn unfollow_user(conn: &mut DbConnection, follower_id: i32, followee_id: i32) -> QueryResult<usize> {
+    diesel::delete(
+        schema::follows::table
+            .filter(schema::follows::follower_id.eq(follower_id))
+            .filter(schema::follows::followee_id.eq(followee_id)),
+    )
+    .execute(conn)
+}
+
+/// フォロワー一覧を取得する関数
+///
+/// 指定された `user_id` のユーザーをフォローしているユーザーを取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: フォローされているユーザーのID
+///
+/// # 戻り値
+/// - フォロワーの `User` オブジェクトのリスト
+pub fn get_followers(conn: &mut DbConnection, user_id: i32) -> QueryResult<Vec<models::User>> {
+    schema::follows::table
+        .inner_join(schema::users::table.on(schema::users::user_id.eq(schema::follows::follower_id)))
+        .filter(schema::follows::followee_id.eq(user_id))
+        .select(schema::users::all_columns)
+        .load(conn)
+}
+
+/// フォロー中のユーザー一覧を取得する関数
+///
+/// 指定された `user_id` のユーザーがフォローしているユーザーを取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: フォローしているユーザーのID
+///
+/// # 戻り値
+/// - フォロー中の `User` オブジェクトのリスト
+pub fn get_following(conn: &mut DbConnection, user_id: i32) -> QueryResult<Vec<models::User>> {
+    schema::follows::table
+        .inner_join(schema::users::table.on(schema::users::user_id.eq(schema::follows::followee_id)))
+        .filter(schema::follows::follower_id.eq(user_id))
+        .select(schema::users::all_columns)
+        .load(conn)
+}
+
+/// フォロワー数を取得する関数
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: フォロワー数を数えるユーザーのID
+///
+/// # 戻り値
+/// - フォロワーの人数
+pub fn count_followers(conn: &mut DbConnection, user_id: i32) -> QueryResult<i64> {
+    schema::follows::table
+        .filter(schema::follows::followee_id.eq(user_id))
+        .count()
+        .get_result(conn)
+}
+
+/// 投稿ビューを組み立てる関数
+///
+/// 指定された `post_id` の投稿、その作成者、コメントとそれぞれのコメント作成者をまとめた `PostView` を返します。
+/// 投稿と作成者の結合、コメントと作成者の結合の2回のクエリで取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 取得する投稿のID
+///
+/// # 戻り値
+/// - 組み立てられた `PostView` オブジェクト
+/// - 投稿が存在しない場合は `NotFound` エラー
+pub fn build_post_view(conn: &mut DbConnection, post_id: i32) -> QueryResult<models::PostView> {
+    // 投稿と作成者を取得
+    let (post, author) = schema::posts::table
+        .inner_join(schema::users::table)
+        .filter(schema::posts::post_id.eq(post_id))
+        .first::<(models::Post, models::User)>(conn)?;
+
+    // コメントとコメント作成者を古い順に取得
+    let comments = schema::comments::table
+        .inner_join(schema::users::table)
+        .filter(schema::comments::post_id.eq(post_id))
+        .order(schema::comments::comment_id.asc())
+        .load::<(models::Comment, models::User)>(conn)?
+        .into_iter()
+        .map(|(comment, author)| models::CommentView { comment, author })
+        .collect();
+
+    Ok(models::PostView { post, author, comments })
+}
+
+/// 権限付き削除の結果
+pub enum DeleteOutcome {
+    /// 削除した
+    Deleted,
+    /// 対象が存在しなかった
+    NotFound,
+}
+
+/// ユーザーが管理者かどうかを判定する関数
+///
+/// 存在しないユーザーは管理者ではないものとして扱います。
+fn is_admin_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<bool> {
+    let is_admin = schema::users::table
+        .find(user_id)
+        .select(schema::users::is_admin)
+        .first::<bool>(conn)
+        .optional()?;
+    Ok(is_admin.unwrap_or(false))
+}
+
+/// 権限を確認して投稿を削除する関数
+///
+/// 削除を要求したユーザーが投稿の作成者または管理者である場合のみ投稿を削除します。
+/// 投稿に付いたコメント、いいね、タグ付けも同じトランザクション内で削除します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 削除する投稿のID
+/// - `requester_user_id`: 削除を要求したユーザーのID
+///
+/// # 戻り値
+/// - 削除した場合は `DeleteOutcome::Deleted`、投稿が存在しない場合は `DeleteOutcome::NotFound`
+/// - 削除する権限がない場合は `BrogError::Forbidden`
+pub fn delete_post_as(conn: &mut DbConnection, post_id: i32, requester_user_id: i32) -> Result<DeleteOutcome, BrogError> {
+    conn.transaction(|conn| {
+        let post = match schema::posts::table.find(post_id).first::<models::Post>(conn).optional()? {
+            Some(post) => post,
+            None => return Ok(DeleteOutcome::NotFound),
+        };
+
+        if post.user_id() != requester_user_id && !is_admin_user(conn, requester_user_id)? {
+            return Err(BrogError::Forbidden);
+        }
+
+        delete_post_dependents(conn, &[post_id])?;
+        delete_post(conn, post_id)?;
+        Ok(DeleteOutcome::Deleted)
+    })
+}
+
+/// 権限を確認してコメントを削除する関数
+///
+/// 削除を要求したユーザーがコメントの作成者、コメントが付いた投稿の作成者、または管理者である場合のみコメントを削除します。
+/// 削除するコメントへの返信は削除せず、親のないコメントとして残します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 削除するコメントのID
+/// - `requester_user_id`: 削除を要求したユーザーのID
+///
+/// # 戻り値
+/// - 削除した場合は `DeleteOutcome::Deleted`、コメントが存在しない場合は `DeleteOutcome::NotFound`
+/// - 削除する権限がない場合は `BrogError::Forbidden`
+pub fn delete_comment_as(conn: &mut DbConnection, comment_id: i32, requester_user_id: i32) -> Result<DeleteOutcome, BrogError> {
+    conn.transaction(|conn| {
+        let (comment, post) = match schema::comments::table
+            .inner_join(schema::posts::table)
+            .filter(schema::comments::comment_id.eq(comment_id))
+            .first::<(models::Comment, models::Post)>(conn)
+            .optional()?
+        {
+            Some(found) => found,
+            None => return Ok(DeleteOutcome::NotFound),
+        };
+
+        let allowed = comment.user_id() == requester_user_id
+            || post.user_id() == requester_user_id
+            || is_admin_user(conn, requester_user_id)?;
+        if !allowed {
+            return Err(BrogError::Forbidden);
+        }
+
+        detach_replies(conn, comment_id)?;
+        delete_comment(conn, comment_id)?;
+        Ok(DeleteOutcome::Deleted)
+    })
+}
+
+/// コメントへの返信の親を外す関数
+///
+/// 返信が親コメントを参照しているとコメントを削除できないため、削除する前に同じトランザクション内で呼び出します。
+/// 親を外した返信は投稿に直接付いたコメントとして扱われます。
+fn detach_replies(conn: &mut DbConnection, comment_id: i32) -> QueryResult<usize> {
+    diesel::update(schema::comments::table.filter(schema::comments::parent_comment_id.eq(comment_id)))
+        .set(schema::comments::parent_comment_id.eq(None::<i32>))
+        .execute(conn)
+}
+
+/// 孤立したコメントを取得する関数
+///
+/// 関連する投稿またはユーザーが既に存在しないコメントを取得します。
+/// データの整合性が崩れていないかを確認するための診断用です。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - 孤立した `Comment` オブジェクトのリスト
+pub fn find_orphaned_comments(conn: &mut DbConnection) -> QueryResult<Vec<models::Comment>> {
+    schema::comments::table
+        .left_join(schema::posts::table)
+        .left_join(schema::users::table)
+        .filter(schema::posts::post_id.nullable().is_null().or(schema::users::user_id.nullable().is_null()))
+        .select(schema::comments::all_columns)
+        .load(conn)
+}
+
+/// 孤立したコメントを削除する関数
+///
+/// `find_orphaned_comments` で見つかったコメントをまとめて削除します。
+/// 孤立したコメントへの返信は、削除する前に同じトランザクション内で親を外します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - 削除された行数
+pub fn delete_orphaned_comments(conn: &mut DbConnection) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let orphan_ids: Vec<i32> = find_orphaned_comments(conn)?
+            .iter()
+            .map(|comment| comment.comment_id())
+            .collect();
+        if orphan_ids.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::update(schema::comments::table.filter(schema::comments::parent_comment_id.eq_any(&orphan_ids)))
+            .set(schema::comments::parent_comment_id.eq(None::<i32>))
+            .execute(conn)?;
+        diesel::delete(schema::comments::table.filter(schema::comments::comment_id.eq_any(&orphan_ids))).execute(conn)
+    })
+}
+
+/// 最近のコメントを取得する関数
+///
+/// 公開中の投稿に付いたコメントを新しい順に取得します。
+/// サイト全体の「最近のコメント」表示用に、コメント作成者とコメントが付いた投稿も合わせて返します。
+/// 下書き、公開日時に達していない予約投稿、無効化されたユーザーの投稿に付いたコメントは含みません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `Comment`、作成者の `PublicUser`、`Post` の組のリスト
+pub fn get_recent_comments(conn: &mut DbConnection, limit: i64) -> QueryResult<Vec<(models::Comment, models::PublicUser, models::Post)>> {
+    // コメント作成者として `users` を結合しているため、投稿者が有効かどうかは別名で確認する
+    let authors = diesel::alias!(schema::users as authors);
+    schema::comments::table
+        .inner_join(schema::users::table)
+        .inner_join(schema::posts::table)
+        .filter(schema::posts::published.eq(true))
+        .filter(
+            schema::posts::user_id.eq_any(
+                authors
+                    .filter(authors.field(schema::users::active).eq(true))
+                    .select(authors.field(schema::users::user_id)),
+            ),
+        )
+        .filter(publish_time_reached())
+        .select((
+            schema::comments::all_columns,
+            models::PublicUser::as_select(),
+            schema::posts::all_columns,
+        ))
+        .order((schema::comments::created_at.desc(), schema::comments::comment_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .load(conn)
+}
+
+/// 日ごとの投稿数を取得する関数
+///
+/// `from` から `to` までの期間(両端を含む)に作成された投稿を作成日ごとに集計します。
+/// 投稿が1件もない日は結果に含まれないため、グラフ表示などで0件の日が必要な場合は呼び出し側で補完してください。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `from`: 集計を開始する日
+/// - `to`: 集計を終了する日
+///
+/// # 戻り値
+/// - 日付と投稿数の組のリスト(日付の昇順)
+pub fn post_counts_by_day(conn: &mut DbConnection, from: NaiveDate, to: NaiveDate) -> QueryResult<Vec<(NaiveDate, i64)>> {
+    let created_date = diesel::dsl::sql::<diesel::sql_types::Date>("DATE(posts.created_at)");
+    let start = from.and_hms_opt(0, 0, 0).expect("midnight is always valid");
+    let end = to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).expect("midnight is always valid");
+
+    schema::posts::table
+        .filter(schema::posts::created_at.ge(start))
+        .filter(schema::posts::created_at.lt(end))
+        .group_by(created_date.clone())
+        .select((created_date.clone(), diesel::dsl::count_star()))
+        .order(created_date.asc())
+        .load(conn)
+}
+
+/// サイトマップ用の投稿一覧を取得する関数
+///
+/// 公開されている投稿のスラッグと最終更新日時を、更新日時の新しい順に取得します。
+/// sitemap.xml の生成に使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - スラッグと `updated_at` の組のリスト
+pub fn get_sitemap_entries(conn: &mut DbConnection) -> QueryResult<Vec<(String, NaiveDateTime)>> {
+    schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .filter(schema::posts::slug.is_not_null())
+        .select((schema::posts::slug.assume_not_null(), schema::posts::updated_at))
+        .order(schema::posts::updated_at.desc())
+        .load(conn)
+}
+
+/// いいねを切り替える関数
+///
+/// ユーザーが投稿にいいねしていなければいいねし、既にいいねしていればいいねを取り消します。
+/// 確認と更新を1つのトランザクションで行います。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: いいねするユーザーのID
+/// - `post_id`: いいねする投稿のID
+///
+/// # 戻り値
+/// - 切り替え後にいいねしている状態であれば `true`
+pub fn toggle_like(conn: &mut DbConnection, user_id: i32, post_id: i32) -> QueryResult<bool> {
+    conn.transaction(|conn| {
+        // 既にいいねしていれば取り消す
+        let removed = diesel::delete(schema::post_likes::table.find((user_id, post_id))).execute(conn)?;
+        if removed > 0 {
+            return Ok(false);
+        }
+
+        diesel::insert_into(schema::post_likes::table)
+            .values(&models::NewPostLike { user_id, post_id })
+            .execute(conn)?;
+        Ok(true)
+    })
+}
+
+/// いいねした投稿を取得する関数
+///
+/// 指定されたユーザーがいいねした投稿を、いいねした日時の新しい順に取得します。
+/// いいねした後で非公開になった投稿、公開日時に達していない予約投稿、無効化されたユーザーの投稿は含みません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: いいねしたユーザーのID
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn get_liked_posts(conn: &mut DbConnection, user_id: i32, limit: i64, offset: i64) -> Result<Vec<models::Post>, BrogError> {
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::post_likes::table
+        .inner_join(schema::posts::table)
+        .filter(schema::post_likes::user_id.eq(user_id))
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .select(schema::posts::all_columns)
+        .order((schema::post_likes::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// IDでレコードの存在を確認するためのトレイト
+///
+/// 入力値の検証などで「指定されたIDのレコードが存在するか」だけを確認したい場合に使用します。
+/// レコード全体を読み込まずに `EXISTS` で確認します。
+pub trait ExistsById {
+    /// 指定された `id` のレコードが存在する場合は `true` を返す
+    fn exists(conn: &mut DbConnection, id: i32) -> QueryResult<bool>;
+}
+
+impl ExistsById for models::User {
+    fn exists(conn: &mut DbConnection, id: i32) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(schema::users::table.find(id))).get_result(conn)
+    }
+}
+
+impl ExistsById for models::Post {
+    fn exists(conn: &mut DbConnection, id: i32) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(schema::posts::table.find(id))).get_result(conn)
+    }
+}
+
+impl ExistsById for models::Comment {
+    fn exists(conn: &mut DbConnection, id: i32) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(schema::comments::table.find(id))).get_result(conn)
+    }
+}
+
+/// 複数の投稿の公開ステータスをまとめて変更する関数
+///
+/// 指定されたIDの投稿すべての `published` を1回の更新で書き換えます。
+/// 公開する場合は、タイトルと本文がそろっているかをすべての投稿について確認してから更新します。
+/// 1件でも公開できない投稿があれば、どの投稿も変更しません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `ids`: 変更する投稿のIDのリスト
+/// - `published`: 設定する公開ステータス
+///
+/// # 戻り値
+/// - 更新された行数
+/// - 公開する場合にタイトルまたは本文が空の投稿があるときは `BrogError::Validation`
+pub fn set_published_for_ids(conn: &mut DbConnection, ids: &[i32], published: bool) -> Result<usize, BrogError> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    conn.transaction(|conn| {
+        if published {
+            let contents = schema::posts::table
+                .filter(schema::posts::post_id.eq_any(ids))
+                .select((schema::posts::post_id, schema::posts::title, schema::posts::post_body))
+                .for_update()
+                .load::<(i32, String, String)>(conn)?;
+            for (post_id, title, body) in &contents {
+                if let Err(BrogError::Validation(message)) = models::validate_publishable(title, body) {
+                    return Err(BrogError::Validation(format!("post {}: {}", post_id, message)));
+                }
+            }
+        }
+
+        let affected_rows = diesel::update(schema::posts::table.filter(schema::posts::post_id.eq_any(ids)))
+            .set(schema::posts::published.eq(published))
+            .execute(conn)?;
+        Ok(affected_rows)
+    })
+}
+
+/// コメント通知の送信先メールアドレスを取得する関数
+///
+/// 投稿の作成者と、その投稿に既にコメントしているユーザーのうち、通知を有効にしているユーザーのメールアドレスを取得します。
+/// 同じメールアドレスは1件にまとめて返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: コメントが付いた投稿のID
+///
+/// # 戻り値
+/// - 通知先のメールアドレスのリスト
+pub fn get_notifiable_emails_for_post_commenters(conn: &mut DbConnection, post_id: i32) -> QueryResult<Vec<String>> {
+    let post_author_ids = schema::posts::table
+        .filter(schema::posts::post_id.eq(post_id))
+        .select(schema::posts::user_id);
+    let commenter_ids = schema::comments::table
+        .filter(schema::comments::post_id.eq(post_id))
+        .select(schema::comments::user_id);
+
+    schema::users::table
+        .filter(schema::users::notifications_enabled.eq(true))
+        .filter(
+            schema::users::user_id
+                .eq_any(post_author_ids)
+                .or(schema::users::user_id.eq_any(commenter_ids)),
+        )
+        .select(schema::users::email)
+        .distinct()
+        .order(schema::users::email.asc())
+        .load(conn)
+}
+
+/// 投稿の閲覧数を1増やす関数
+///
+/// 閲覧は投稿の編集ではないため、`updated_at` は変更しません。
+/// MySQLでは `updated_at` を同じ値で明示的に指定して `ON UPDATE CURRENT_TIMESTAMP` を止め、
+/// PostgreSQLではトリガーが閲覧数だけの変更を無視します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 閲覧された投稿のID
+///
+/// # 戻り値
+/// - 更新された行数
+pub fn increment_view_count(conn: &mut DbConnection, post_id: i32) -> QueryResult<usize> {
+    diesel::update(schema::posts::table.find(post_id))
+        .set((
+            schema::posts::view_count.eq(schema::posts::view_count + 1),
+            schema::posts::updated_at.eq(schema::posts::updated_at),
+        ))
+        .execute(conn)
+}
+
+/// 投稿の統計情報を取得する関数
+///
+/// 投稿のいいね数、コメント数、閲覧数を1つのトランザクション内で取得し、`PostStats` にまとめて返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 統計情報を取得する投稿のID
+///
+/// # 戻り値
+/// - 投稿の `PostStats`
+/// - 投稿が存在しない場合は `NotFound` エラー
+pub fn get_post_stats(conn: &mut DbConnection, post_id: i32) -> QueryResult<models::PostStats> {
+    conn.transaction(|conn| {
+        let views = schema::posts::table
+            .find(post_id)
+            .select(schema::posts::view_count)
+            .first::<i32>(conn)?;
+
+        let likes = schema::post_likes::table
+            .filter(schema::post_likes::post_id.eq(post_id))
+            .count()
+            .get_result(conn)?;
+
+        let comments = schema::comments::table
+            .filter(schema::comments::post_id.eq(post_id))
+            .count()
+            .get_result(conn)?;
+
+        Ok(models::PostStats {
+            likes,
+            comments,
+            views: i64::from(views),
+        })
+    })
+}
+
+/// 活動のないユーザーを取得する関数
+///
+/// 投稿もコメントも1件もないユーザーを取得します。
+/// 放置されたアカウントを整理する際に使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - 活動のない `PublicUser` のリスト
+pub fn find_users_with_no_activity(conn: &mut DbConnection) -> QueryResult<Vec<models::PublicUser>> {
+    use diesel::dsl::{exists, not};
+
+    schema::users::table
+        .filter(not(exists(
+            schema::posts::table.filter(schema::posts::user_id.eq(schema::users::user_id)),
+        )))
+        .filter(not(exists(
+            schema::comments::table.filter(schema::comments::user_id.eq(schema::users::user_id)),
+        )))
+        .select(models::PublicUser::as_select())
+        .order(schema::users::user_id.asc())
+        .load(conn)
+}
+
+/// コメントの階層をたどる際の最大回数
+/// 親子関係が循環している場合に処理が終わらなくなることを防ぐ。
+const MAX_COMMENT_DEPTH: u32 = 1000;
+
+/// コメントの階層の深さを取得する関数
+///
+/// `parent_comment_id` を投稿への直接のコメントまでたどり、その回数を返します。
+/// 投稿への直接のコメントの深さは0です。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 深さを求めるコメントのID
+///
+/// # 戻り値
+/// - コメントの深さ
+/// - コメントが存在しない場合は `BrogError::NotFound`
+/// - `MAX_COMMENT_DEPTH` 回たどっても終わらない場合(親子関係の循環など)は `BrogError::Validation`
+pub fn get_comment_depth(conn: &mut DbConnection, comment_id: i32) -> Result<u32, BrogError> {
+    let mut depth = 0;
+    let mut parent = schema::comments::table
+        .find(comment_id)
+        .select(schema::comments::parent_comment_id)
+        .first::<Option<i32>>(conn)?;
+
+    while let Some(parent_id) = parent {
+        if depth >= MAX_COMMENT_DEPTH {
+            return Err(BrogError::Validation(format!(
+                "comment {} exceeds the maximum thread depth of {}",
+                comment_id, MAX_COMMENT_DEPTH
+            )));
+        }
+        depth += 1;
+        parent = schema::comments::table
+            .find(parent_id)
+            .select(schema::comments::parent_comment_id)
+            .first::<Option<i32>>(conn)?;
+    }
+
+    Ok(depth)
+}
+
+/// 指定したユーザー以外の投稿を取得する関数
+///
+/// 「他の作者を見つける」フィード用に、指定されたユーザーが作成したものを除く公開済みの投稿を新しい順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `exclude_user_id`: 除外するユーザーのID
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn get_posts_excluding_user(conn: &mut DbConnection, exclude_user_id: i32, limit: i64, offset: i64) -> Result<Vec<models::Post>, BrogError> {
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .filter(schema::posts::user_id.ne(exclude_user_id))
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// 2つの投稿の表示順を入れ替える関数
+///
+/// 投稿の `sort_order` の値を1つのトランザクション内で入れ替えます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id_a`: 入れ替える投稿のID
+/// - `post_id_b`: 入れ替える投稿のID
+///
+/// # 戻り値
+/// - どちらかの投稿が存在しない場合は `NotFound` エラー
+pub fn swap_post_order(conn: &mut DbConnection, post_id_a: i32, post_id_b: i32) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let order_a = schema::posts::table
+            .find(post_id_a)
+            .select(schema::posts::sort_order)
+            .first::<i32>(conn)?;
+        let order_b = schema::posts::table
+            .find(post_id_b)
+            .select(schema::posts::sort_order)
+            .first::<i32>(conn)?;
+
+        diesel::update(schema::posts::table.find(post_id_a))
+            .set(schema::posts::sort_order.eq(order_b))
+            .execute(conn)?;
+        diesel::update(schema::posts::table.find(post_id_b))
+            .set(schema::posts::sort_order.eq(order_a))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// 表示順で投稿を取得する関数
+///
+/// 投稿を `sort_order` の昇順に取得します。表示順が同じ場合は `post_id` の昇順となります。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn list_posts_by_order(conn: &mut DbConnection, limit: i64, offset: i64) -> Result<Vec<models::Post>, BrogError> {
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .order((schema::posts::sort_order.asc(), schema::posts::post_id.asc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// スレッド形式のコメント一覧を取得する関数
+///
+/// 投稿に付いたコメントを、返信が返信先のコメントの直後に来る順番に並べ、それぞれの深さと合わせて返します。
+/// 深さは投稿への直接のコメントが0で、返信ごとに1ずつ増えます。インデント付きの表示に使用します。
+/// 返信先が循環しているコメントも、循環の中で最も古いコメントを深さ0として含めます。
+/// コメントは1回のクエリで取得し、並べ替えはメモリ上で行います。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: コメントを取得する投稿のID
+///
+/// # 戻り値
+/// - `Comment` と深さの組のリスト
+pub fn get_thread_flat(conn: &mut DbConnection, post_id: i32) -> QueryResult<Vec<(models::Comment, u32)>> {
+    let comments = schema::comments::table
+        .filter(schema::comments::post_id.eq(post_id))
+        .order(schema::comments::comment_id.asc())
+        .load::<models::Comment>(conn)?;
+
+    let (roots, mut children) = group_comment_replies(comments);
+
+    // 深さ優先で並べる。スタックには後で取り出すものから積む
+    let mut flat = Vec::new();
+    let mut stack: Vec<(models::Comment, u32)> = roots.into_iter().rev().map(|comment| (comment, 0)).collect();
+    while let Some((comment, depth)) = stack.pop() {
+        if let Some(replies) = children.remove(&comment.comment_id()) {
+            stack.extend(replies.into_iter().rev().map(|reply| (reply, depth + 1)));
+        }
+        flat.push((comment, depth));
+    }
+
+    Ok(flat)
+}
+
+/// コメントを投稿への直接のコメントと、返信先ごとの返信に分ける関数
+///
+/// 返信先のコメントが同じ投稿に存在しない返信は、投稿への直接のコメントとして扱います。
+/// 返信先をたどると循環しているコメントは、循環の中で最も前にあるコメントを投稿への直接のコメントとして扱い、
+/// どのコメントも結果から欠けないようにします。
+/// それぞれのリストは渡された順番を保ちます。
+fn group_comment_replies(comments: Vec<models::Comment>) -> (Vec<models::Comment>, HashMap<i32, Vec<models::Comment>>) {
+    let position: HashMap<i32, usize> = comments
+        .iter()
+        .enumerate()
+        .map(|(index, comment)| (comment.comment_id(), index))
+        .collect();
+    let parent_of: HashMap<i32, i32> = comments
+        .iter()
+        .filter_map(|comment| {
+            comment
+                .parent_comment_id()
+                .filter(|parent_id| position.contains_key(parent_id))
+                .map(|parent_id| (comment.comment_id(), parent_id))
+        })
+        .collect();
+
+    // 返信先をたどり、根に届かずに同じコメントへ戻ってきた場合は循環している
+    let mut promoted = HashSet::new();
+    let mut settled = HashSet::new();
+    for comment in &comments {
+        let mut path = Vec::new();
+        let mut on_path = HashMap::new();
+        let mut current = comment.comment_id();
+        while !settled.contains(&current) {
+            if let Some(&start) = on_path.get(&current) {
+                let cycle_root = path[start..]
+                    .iter()
+                    .copied()
+                    .min_by_key(|id| position[id])
+                    .expect("a cycle contains at least one comment");
+                promoted.insert(cycle_root);
+                break;
+            }
+            on_path.insert(current, path.len());
+            path.push(current);
+            match parent_of.get(&current) {
+                Some(parent_id) => current = *parent_id,
+                None => break,
+            }
+        }
+        settled.extend(path);
+    }
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<i32, Vec<models::Comment>> = HashMap::new();
+    for comment in comments {
+        match parent_of.get(&comment.comment_id()) {
+            Some(parent_id) if !promoted.contains(&comment.comment_id()) => children.entry(*parent_id).or_default().push(comment),
+            _ => roots.push(comment),
+        }
+    }
+
+    (roots, children)
+}
+
+/// ユーザーの最終活動日時を更新する関数
+///
+/// ユーザーの `last_active_at` を現在日時(データベースの時刻)に更新します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 活動したユーザーのID
+pub fn touch_last_active(conn: &mut DbConnection, user_id: i32) -> QueryResult<()> {
+    diesel::update(schema::users::table.find(user_id))
+        .set(schema::users::last_active_at.eq(diesel::dsl::sql::<diesel::dsl::SqlTypeOf<schema::users::last_active_at>>("CURRENT_TIMESTAMP(6)")))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// 最近活動したユーザーを取得する関数
+///
+/// `since` 以降に活動したユーザーを、最終活動日時の新しい順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `since`: この日時以降に活動したユーザーを取得する
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `PublicUser` のリスト
+pub fn get_recently_active_users(conn: &mut DbConnection, since: NaiveDateTime, limit: i64) -> QueryResult<Vec<models::PublicUser>> {
+    schema::users::table
+        .filter(schema::users::last_active_at.ge(since))
+        .select(models::PublicUser::as_select())
+        .order(schema::users::last_active_at.desc())
+        .limit(pagination::clamp_limit(limit))
+        .load(conn)
+}
+
+/// 作成者名付きで投稿を取得する関数
+///
+/// フィード表示用に、公開済みの投稿を作成者の名前と合わせて新しい順に取得します。
+/// `User` 全体ではなく名前だけを取得するため、転送するデータ量を抑えられます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` と作成者の名前の組のリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn list_posts_with_author_name(conn: &mut DbConnection, limit: i64, offset: i64) -> Result<Vec<(models::Post, String)>, BrogError> {
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .inner_join(schema::users::table)
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::users::active.eq(true))
+        .filter(publish_time_reached())
+        .select((schema::posts::all_columns, schema::users::name))
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// ユーザー削除の影響を確認する関数
+///
+/// ユーザーを削除する前に、そのユーザーが作成した投稿とコメントの件数を数えます。
+/// 実際の削除は行いません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 削除を検討しているユーザーのID
+///
+/// # 戻り値
+/// - 投稿数とコメント数を持つ `DeletionImpact`
+pub fn delete_user_impact(conn: &mut DbConnection, user_id: i32) -> QueryResult<models::DeletionImpact> {
+    conn.transaction(|conn| {
+        let posts = schema::posts::table
+            .filter(schema::posts::user_id.eq(user_id))
+            .count()
+            .get_result(conn)?;
+
+        let comments = schema::comments::table
+            .filter(schema::comments::user_id.eq(user_id))
+            .count()
+            .get_result(conn)?;
+
+        Ok(models::DeletionImpact { posts, comments })
+    })
+}
+
+/// コメント数の多いユーザーを取得する関数
+///
+/// コミュニティページのランキング用に、ユーザーをコメント数の多い順に取得します。
+/// コメントが1件もないユーザーは含みません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `PublicUser` とコメント数の組のリスト
+pub fn get_top_commenters(conn: &mut DbConnection, limit: i64) -> QueryResult<Vec<(models::PublicUser, i64)>> {
+    schema::users::table
+        .inner_join(schema::comments::table)
+        .group_by(schema::users::user_id)
+        .select((models::PublicUser::as_select(), diesel::dsl::count_star()))
+        .order((diesel::dsl::count_star().desc(), schema::users::user_id.asc()))
+        .limit(pagination::clamp_limit(limit))
+        .load(conn)
+}
+
+/// 投稿にコメントしたユーザーを取得する関数
+///
+/// 「@」による参加者への通知用に、投稿にコメントしたユーザーを重複なく取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 対象の投稿のID
+/// - `exclude_author`: `true` の場合は投稿の作成者を結果から除く
+///
+/// # 戻り値
+/// - コメントした `PublicUser` のリスト
+pub fn get_comment_participants(conn: &mut DbConnection, post_id: i32, exclude_author: bool) -> QueryResult<Vec<models::PublicUser>> {
+    let commenter_ids = schema::comments::table
+        .filter(schema::comments::post_id.eq(post_id))
+        .select(schema::comments::user_id);
+
+    let mut query = schema::users::table
+        .filter(schema::users::user_id.eq_any(commenter_ids))
+        .select(models::PublicUser::as_select())
+        .order(schema::users::user_id.asc())
+        .into_boxed();
+
+    if exclude_author {
+        let author_ids = schema::posts::table
+            .filter(schema::posts::post_id.eq(post_id))
+            .select(schema::posts::user_id);
+        query = query.filter(schema::users::user_id.ne_all(author_ids));
+    }
+
+    query.load(conn)
+}
+
+/// すべての投稿に対して処理を行う関数
+///
+/// ブログ全体のエクスポートなど、すべての投稿を扱う処理のために投稿を `batch_size` 件ずつ読み込み、1件ごとに `f` を呼び出します。
+/// 一度に読み込むのは `batch_size` 件までのため、投稿数が多くてもメモリの使用量を抑えられます。
+/// 投稿は `post_id` の昇順に渡されます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `batch_size`: 1回のクエリで読み込む件数(1未満の場合は1として扱う)
+/// - `f`: 投稿ごとに呼び出す関数
+pub fn for_each_post(conn: &mut DbConnection, batch_size: i64, mut f: impl FnMut(&models::Post)) -> QueryResult<()> {
+    let batch_size = batch_size.max(1);
+    let mut last_post_id = None;
+
+    loop {
+        let mut query = schema::posts::table
+            .order(schema::posts::post_id.asc())
+            .limit(batch_size)
+            .into_boxed();
+        // オフセットではなく前回の最後のIDから読み込むことで、後半のページでも速度が落ちないようにする
+        if let Some(last_post_id) = last_post_id {
+            query = query.filter(schema::posts::post_id.gt(last_post_id));
+        }
+
+        let batch = query.load::<models::Post>(conn)?;
+        for post in &batch {
+            f(post);
+        }
+
+        match batch.last() {
+            Some(post) if batch.len() as i64 == batch_size => last_post_id = Some(post.post_id()),
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// ユーザーのタイムラインを取得する関数
+///
+/// ユーザーが作成した投稿とコメントを作成日時の新しい順に混ぜて取得します。
+/// 投稿は公開済みで公開日時に達したものだけを含めます。
+/// 投稿とコメントをそれぞれ最大 `limit` 件取得し、メモリ上で並べ替えてから `limit` 件に絞ります。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: タイムラインを取得するユーザーのID
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `TimelineItem` のリスト
+pub fn get_user_timeline(conn: &mut DbConnection, user_id: i32, limit: i64) -> QueryResult<Vec<models::TimelineItem>> {
+    let limit = pagination::clamp_limit(limit);
+
+    let posts = schema::posts::table
+        .filter(schema::posts::user_id.eq(user_id))
+        .filter(schema::posts::published.eq(true))
+        .filter(publish_time_reached())
+        .order(schema::posts::created_at.desc())
+        .limit(limit)
+        .load::<models::Post>(conn)?;
+
+    let comments = schema::comments::table
+        .filter(schema::comments::user_id.eq(user_id))
+        .order(schema::comments::created_at.desc())
+        .limit(limit)
+        .load::<models::Comment>(conn)?;
+
+    let mut timeline: Vec<models::TimelineItem> = posts
+        .into_iter()
+        .map(models::TimelineItem::Post)
+        .chain(comments.into_iter().map(models::TimelineItem::Comment))
+        .collect();
+    timeline.sort_by_key(|item| std::cmp::Reverse(item.created_at()));
+    timeline.truncate(limit as usize);
+
+    Ok(timeline)
+}
+
+/// 投稿を検索する関数
+///
+/// タイトルまたは本文に `query` を含む公開済みの投稿を新しい順に取得します。
+/// 大文字・小文字は区別しません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `query`: 検索する文字列
+///
+/// # 戻り値
+/// - 見つかった `Post` オブジェクトのリスト
+pub fn search_posts(conn: &mut DbConnection, query: &str) -> QueryResult<Vec<models::Post>> {
+    let pattern = format!("%{}%", sql::escape_like(&query.to_lowercase()));
+
+    schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .filter(
+            sql::lower(schema::posts::title)
+                .like(&pattern)
+                .or(sql::lower(schema::posts::post_body).like(&pattern)),
+        )
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .load(conn)
+}
+
+/// 投稿へのコメントの受け付けを切り替える関数
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 対象の投稿のID
+/// - `enabled`: `true` でコメントを受け付け、`false` でコメントを締め切る
+///
+/// # 戻り値
+/// - 更新された行数
+pub fn set_comments_enabled(conn: &mut DbConnection, post_id: i32, enabled: bool) -> QueryResult<usize> {
+    diesel::update(schema::posts::table.find(post_id))
+        .set(schema::posts::comments_enabled.eq(enabled))
+        .execute(conn)
+}
+
+/// IDまたはスラッグで投稿を取得する関数
+///
+/// URLに含まれる識別子から投稿を取得します。
+/// `identifier` が数値として解釈できる場合は投稿IDとして、それ以外の場合はスラッグとして検索します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `identifier`: 投稿IDの文字列またはスラッグ
+///
+/// # 戻り値
+/// - 見つかった場合は `Some(Post)`、存在しない場合は `None`
+pub fn resolve_post(conn: &mut DbConnection, identifier: &str) -> QueryResult<Option<models::Post>> {
+    match identifier.parse::<i32>() {
+        Ok(post_id) => schema::posts::table.find(post_id).first(conn).optional(),
+        Err(_) => schema::posts::table
+            .filter(schema::posts::slug.eq(identifier))
+            .first(conn)
+            .optional(),
+    }
+}
+
+/// 指定日時以降のコメント数を数える関数
+///
+/// ユーザーが `since` 以降に作成したコメントの数を数えます。
+/// 「1分あたり最大N件」のようなコメント投稿の回数制限を呼び出し側で行うために使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: コメントを数えるユーザーのID
+/// - `since`: この日時以降のコメントを数える
+///
+/// # 戻り値
+/// - コメント数
+pub fn count_comments_since(conn: &mut DbConnection, user_id: i32, since: NaiveDateTime) -> QueryResult<i64> {
+    schema::comments::table
+        .filter(schema::comments::user_id.eq(user_id))
+        .filter(schema::comments::created_at.ge(since))
+        .count()
+        .get_result(conn)
+}
+
+/// タグ名を正規化する関数
+///
+/// 「Rust」と「 rust 」が別のタグにならないよう、前後の空白を取り除いて小文字にします。
+pub fn normalize_tag_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// 新しいタグを作成する関数
+///
+/// タグ名は `normalize_tag_name` で正規化してから保存します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `name`: タグ名
+///
+/// # 戻り値
+/// - 作成された `Tag` オブジェクト
+pub fn create_tag(conn: &mut DbConnection, name: &str) -> QueryResult<models::Tag> {
+    let new_tag = models::NewTag {
+        name: normalize_tag_name(name),
+    };
+
+    // 新しいタグをテーブルに挿入
+    diesel::insert_into(schema::tags::table)
+        .values(&new_tag)
+        .execute(conn)?;
+
+    // 最後に挿入されたタグを取得
+    schema::tags::table.order(schema::tags::tag_id.desc()).first(conn)
+}
+
+/// 投稿にタグを付ける関数
+///
+/// `tag_name` のタグが存在しない場合は作成してから投稿に関連付けます。
+/// 既に同じタグが付いている場合は何もしません。
+/// タグ名は大文字・小文字と前後の空白を区別せずに比較します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: タグを付ける投稿のID
+/// - `tag_name`: 付けるタグの名前
+///
+/// # 戻り値
+/// - 投稿に付けた `Tag` オブジェクト
+pub fn add_tag_to_post(conn: &mut DbConnection, post_id: i32, tag_name: &str) -> QueryResult<models::Tag> {
+    let normalized = normalize_tag_name(tag_name);
+
+    conn.transaction(|conn| {
+        // 正規化する前に作成されたタグにも一致するよう、データベース側でも正規化して比較する
+        let tag = match schema::tags::table
+            .filter(sql::lower(sql::trim(schema::tags::name)).eq(&normalized))
+            .order(schema::tags::tag_id.asc())
+            .first::<models::Tag>(conn)
+            .optional()?
+        {
+            Some(tag) => tag,
+            None => create_tag(conn, &normalized)?,
+        };
+
+        add_tag_to_posts(conn, tag.tag_id(), &[post_id])?;
+        Ok(tag)
+    })
+}
+
+/// タグが付いた投稿を取得する関数
+///
+/// `tag_name` のタグが付いた公開済みの投稿を新しい順に取得します。
+/// タグ名は大文字・小文字と前後の空白を区別せずに比較します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `tag_name`: タグの名前
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+pub fn get_posts_by_tag(conn: &mut DbConnection, tag_name: &str) -> QueryResult<Vec<models::Post>> {
+    schema::post_tags::table
+        .inner_join(schema::posts::table)
+        .inner_join(schema::tags::table)
+        .filter(sql::lower(sql::trim(schema::tags::name)).eq(normalize_tag_name(tag_name)))
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .select(schema::posts::all_columns)
+        .distinct()
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .load(conn)
+}
+
+/// 複数の投稿にまとめてタグを付ける関数
+///
+/// 指定された投稿すべてに `tag_id` のタグを関連付けます。
+/// 既に同じタグが付いている投稿は飛ばします。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `tag_id`: 付けるタグのID
+/// - `post_ids`: タグを付ける投稿のIDのリスト
+///
+/// # 戻り値
+/// - 新たに関連付けた件数
+pub fn add_tag_to_posts(conn: &mut DbConnection, tag_id: i32, post_ids: &[i32]) -> QueryResult<usize> {
+    if post_ids.is_empty() {
+        return Ok(0);
+    }
+
+    conn.transaction(|conn| {
+        // 既にタグが付いている投稿を除く
+        let tagged: HashSet<i32> = schema::post_tags::table
+            .filter(schema::post_tags::tag_id.eq(tag_id))
+            .filter(schema::post_tags::post_id.eq_any(post_ids))
+            .select(schema::post_tags::post_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .collect();
+
+        let mut seen = HashSet::new();
+        let new_post_tags: Vec<models::NewPostTag> = post_ids
+            .iter()
+            .filter(|post_id| !tagged.contains(post_id) && seen.insert(**post_id))
+            .map(|&post_id| models::NewPostTag { post_id, tag_id })
+            .collect();
+        if new_post_tags.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::insert_into(schema::post_tags::table)
+            .values(&new_post_tags)
+            .execute(conn)
+    })
+}
+
+/// タグが付いていない投稿を取得する関数
+///
+/// 編集作業用に、タグが1つも付いていない公開済みの投稿を新しい順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn get_untagged_posts(conn: &mut DbConnection, limit: i64, offset: i64) -> Result<Vec<models::Post>, BrogError> {
+    use diesel::dsl::{exists, not};
+
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .filter(not(exists(
+            schema::post_tags::table.filter(schema::post_tags::post_id.eq(schema::posts::post_id)),
+        )))
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// 本文が空の投稿を取得する関数
+///
+/// データのインポート時の不具合などを見つけるため、本文が空または空白(スペース、タブ、改行)のみの投稿を取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - 本文が空の `Post` オブジェクトのリスト
+pub fn find_posts_with_empty_body(conn: &mut DbConnection) -> QueryResult<Vec<models::Post>> {
+    // TRIM() はスペースしか取り除かないため、先にタブと改行を取り除く
+    let without_tabs_and_newlines = sql::replace(
+        sql::replace(sql::replace(schema::posts::post_body, "\t", ""), "\n", ""),
+        "\r",
+        "",
+    );
+
+    schema::posts::table
+        .filter(sql::trim(without_tabs_and_newlines).eq(""))
+        .order(schema::posts::post_id.asc())
+        .load(conn)
+}
+
+/// 投稿とコメントを残してユーザーを削除する関数
+///
+/// ユーザーの投稿とコメントを「削除されたユーザー」用のアカウントに付け替えてから、ユーザーを削除します。
+/// フォロー、いいね、パスワードリセットトークンはユーザーと一緒に削除します。
+/// すべての処理は1つのトランザクションで行います。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 削除するユーザーのID
+/// - `placeholder_user_id`: 投稿とコメントの付け替え先となるアカウントのID
+///
+/// # 戻り値
+/// - 削除された行数
+/// - `user_id` と `placeholder_user_id` が同じ場合は `BrogError::Validation`
+/// - 付け替え先のアカウントが存在しない場合は `BrogError::NotFound`
+pub fn delete_user_preserving_content(conn: &mut DbConnection, user_id: i32, placeholder_user_id: i32) -> Result<usize, BrogError> {
+    if user_id == placeholder_user_id {
+        return Err(BrogError::Validation("placeholder user must differ from the deleted user".to_string()));
+    }
+
+    conn.transaction(|conn| {
+        if !<models::User as ExistsById>::exists(conn, placeholder_user_id)? {
+            return Err(BrogError::NotFound);
+        }
+
+        // 投稿とコメントを付け替える
+        diesel::update(schema::posts::table.filter(schema::posts::user_id.eq(user_id)))
+            .set(schema::posts::user_id.eq(placeholder_user_id))
+            .execute(conn)?;
+        diesel::update(schema::comments::table.filter(schema::comments::user_id.eq(user_id)))
+            .set(schema::comments::user_id.eq(placeholder_user_id))
+            .execute(conn)?;
+
+        // ユーザーに紐付くその他のデータを削除する
+        diesel::delete(
+            schema::follows::table.filter(
+                schema::follows::follower_id
+                    .eq(user_id)
+                    .or(schema::follows::followee_id.eq(user_id)),
+            ),
+        )
+        .execute(conn)?;
+        diesel::delete(schema::post_likes::table.filter(schema::post_likes::user_id.eq(user_id))).execute(conn)?;
+        diesel::delete(schema::password_resets::table.filter(schema::password_resets::user_id.eq(user_id))).execute(conn)?;
+
+        let affected_rows = delete_user(conn, user_id)?;
+        Ok(affected_rows)
+    })
+}
+
+/// 投稿の作成年を求めるSQL式
+#[cfg(not(feature = "postgres"))]
+const POST_CREATED_YEAR_SQL: &str = "YEAR(posts.created_at)";
+#[cfg(feature = "postgres")]
+const POST_CREATED_YEAR_SQL: &str = "CAST(EXTRACT(YEAR FROM posts.created_at) AS INTEGER)";
+
+/// 投稿がある年の一覧を取得する関数
+///
+/// アーカイブのナビゲーション用に、公開済みの投稿が作成された年を重複なく新しい順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - 年のリスト
+pub fn get_post_years(conn: &mut DbConnection) -> QueryResult<Vec<i32>> {
+    let created_year = diesel::dsl::sql::<diesel::sql_types::Integer>(POST_CREATED_YEAR_SQL);
+
+    schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .select(created_year.clone())
+        .distinct()
+        .order(created_year.desc())
+        .load(conn)
+}
+
+/// 指定した月の投稿を取得する関数
+///
+/// 月別アーカイブページ用に、`year` 年 `month` 月に作成された公開済みの投稿を新しい順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `year`: 年
+/// - `month`: 月(1〜12)
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `month` が1〜12の範囲外、または年が扱えない値の場合は `BrogError::Validation`
+pub fn get_posts_by_month(conn: &mut DbConnection, year: i32, month: u32) -> Result<Vec<models::Post>, BrogError> {
+    if !(1..=12).contains(&month) {
+        return Err(BrogError::Validation(format!("month must be between 1 and 12: {}", month)));
+    }
+
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    let (start, end) = match (NaiveDate::from_ymd_opt(year, month, 1), NaiveDate::from_ymd_opt(next_year, next_month, 1)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Err(BrogError::Validation(format!("year is out of range: {}", year))),
+    };
+
+    let posts = schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .filter(schema::posts::created_at.ge(start.and_hms_opt(0, 0, 0).expect("midnight is always valid")))
+        .filter(schema::posts::created_at.lt(end.and_hms_opt(0, 0, 0).expect("midnight is always valid")))
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// コメントツリーを取得する関数
+///
+/// 投稿に付いたコメントを、返信を子に持つツリー構造にして返します。
+/// 返信先が循環しているコメントも、循環の中で最も古いコメントを根として含めます。
+/// コメントは1回のクエリで取得し、ツリーの組み立てはメモリ上で行います。
+/// 同じ階層のコメントは古い順に並びます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: コメントを取得する投稿のID
+///
+/// # 戻り値
+/// - 投稿への直接のコメントを根とする `CommentNode` のリスト
+pub fn get_comment_tree(conn: &mut DbConnection, post_id: i32) -> QueryResult<Vec<models::CommentNode>> {
+    let comments = schema::comments::table
+        .filter(schema::comments::post_id.eq(post_id))
+        .order(schema::comments::comment_id.asc())
+        .load::<models::Comment>(conn)?;
+
+    let (roots, mut children) = group_comment_replies(comments);
+    Ok(roots
+        .into_iter()
+        .map(|comment| build_comment_node(comment, &mut children))
+        .collect())
+}
+
+/// コメントとその返信から `CommentNode` を組み立てる関数
+fn build_comment_node(comment: models::Comment, children: &mut HashMap<i32, Vec<models::Comment>>) -> models::CommentNode {
+    let replies = children.remove(&comment.comment_id()).unwrap_or_default();
+    models::CommentNode {
+        children: replies
+            .into_iter()
+            .map(|reply| build_comment_node(reply, children))
+            .collect(),
+        comment,
+    }
+}
+
+/// 追記する際に本文と追記の間に入れる区切り
+const POST_APPEND_SEPARATOR: &str = "\n\n";
+
+/// 投稿の本文に追記する関数
+///
+/// ライブブログの更新など、既存の本文の末尾に `addition` を区切り付きで追加します。
+/// 本文の読み込みから更新までを行ロック付きのトランザクションで行うため、同時に追記しても内容が失われません。
+/// 本文が空の場合は区切りを付けません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 追記する投稿のID
+/// - `addition`: 追記する文章
+///
+/// # 戻り値
+/// - 更新後の `Post` オブジェクト
+/// - 投稿が存在しない場合は `NotFound` エラー
+pub fn append_to_post_body(conn: &mut DbConnection, post_id: i32, addition: &str) -> QueryResult<models::Post> {
+    conn.transaction(|conn| {
+        let body = schema::posts::table
+            .find(post_id)
+            .select(schema::posts::post_body)
+            .for_update()
+            .first::<String>(conn)?;
+
+        let new_body = if body.is_empty() {
+            addition.to_string()
+        } else {
+            format!("{}{}{}", body, POST_APPEND_SEPARATOR, addition)
+        };
+
+        diesel::update(schema::posts::table.find(post_id))
+            .set((
+                schema::posts::post_body.eq(&new_body),
+                schema::posts::version.eq(schema::posts::version + 1),
+            ))
+            .execute(conn)?;
+        record_post_revision(conn, post_id, &new_body)?;
+
+        schema::posts::table.find(post_id).first(conn)
+    })
+}
+
+/// 同じ投稿に付いた他のコメントを取得する関数
+///
+/// 「このスレッドの他のコメント」表示用に、指定されたコメントと同じ投稿に付いたコメントを新しい順に取得します。
+/// 指定されたコメント自体は含みません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 基準となるコメントのID
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `Comment` オブジェクトのリスト
+/// - 基準となるコメントが存在しない場合は `NotFound` エラー
+pub fn get_sibling_comments(conn: &mut DbConnection, comment_id: i32, limit: i64) -> QueryResult<Vec<models::Comment>> {
+    let post_id = schema::comments::table
+        .find(comment_id)
+        .select(schema::comments::post_id)
+        .first::<i32>(conn)?;
+
+    schema::comments::table
+        .filter(schema::comments::post_id.eq(post_id))
+        .filter(schema::comments::comment_id.ne(comment_id))
+        .order((schema::comments::created_at.desc(), schema::comments::comment_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .load(conn)
+}
+
+/// ユーザーの投稿IDの一覧を取得する関数
+///
+/// 権限チェックのキャッシュなど、投稿IDだけが必要な場合に `Post` 全体を読み込まずに済ませるために使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 投稿を作成したユーザーのID
+///
+/// # 戻り値
+/// - 投稿IDのリスト(昇順)
+pub fn get_post_ids_by_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<Vec<i32>> {
+    schema::posts::table
+        .filter(schema::posts::user_id.eq(user_id))
+        .select(schema::posts::post_id)
+        .order(schema::posts::post_id.asc())
+        .load(conn)
+}
+
+/// モデレーション待ちの投稿を取得する関数
+///
+/// 確認が必要とされた投稿を、古いものから順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn get_moderation_queue(conn: &mut DbConnection, limit: i64, offset: i64) -> Result<Vec<models::Post>, BrogError> {
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .filter(schema::posts::needs_review.eq(true))
+        .order((schema::posts::created_at.asc(), schema::posts::post_id.asc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// 投稿を承認する関数
+///
+/// モデレーション待ちの状態を解除し、投稿を公開します。
+/// タイトルまたは本文が空の投稿は公開できないため、承認せずにモデレーション待ちのまま残します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 承認する投稿のID
+///
+/// # 戻り値
+/// - 更新された行数
+/// - タイトルまたは本文が空の場合は `BrogError::Validation`
+pub fn approve_post(conn: &mut DbConnection, post_id: i32) -> Result<usize, BrogError> {
+    conn.transaction(|conn| {
+        let content = schema::posts::table
+            .find(post_id)
+            .select((schema::posts::title, schema::posts::post_body))
+            .for_update()
+            .first::<(String, String)>(conn)
+            .optional()?;
+        let (title, body) = match content {
+            Some(content) => content,
+            None => return Ok(0),
+        };
+        models::validate_publishable(&title, &body)?;
+
+        let affected_rows = diesel::update(schema::posts::table.find(post_id))
+            .set((schema::posts::needs_review.eq(false), schema::posts::published.eq(true)))
+            .execute(conn)?;
+        Ok(affected_rows)
+    })
+}
+
+/// 日ごとのユーザー登録数を取得する関数
+///
+/// `from` から `to` までの期間(両端を含む)に登録されたユーザーを登録日ごとに集計します。
+/// 登録が1件もない日は結果に含まれません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `from`: 集計を開始する日
+/// - `to`: 集計を終了する日
+///
+/// # 戻り値
+/// - 日付と登録数の組のリスト(日付の昇順)
+pub fn user_signups_by_day(conn: &mut DbConnection, from: NaiveDate, to: NaiveDate) -> QueryResult<Vec<(NaiveDate, i64)>> {
+    let created_date = diesel::dsl::sql::<diesel::sql_types::Date>("DATE(users.created_at)");
+    let start = from.and_hms_opt(0, 0, 0).expect("midnight is always valid");
+    let end = to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).expect("midnight is always valid");
+
+    schema::users::table
+        .filter(schema::users::created_at.ge(start))
+        .filter(schema::users::created_at.lt(end))
+        .group_by(created_date.clone())
+        .select((created_date.clone(), diesel::dsl::count_star()))
+        .order(created_date.asc())
+        .load(conn)
+}
+
+/// カーソルを使って公開済みの投稿を取得する関数
+///
+/// 投稿IDの降順(新しい順)に取得します。
+/// オフセットを使う方法と異なり、取得の途中で投稿が追加されても結果がずれません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `after_post_id`: 前回取得した最後の投稿のID。`None` の場合は先頭から取得する
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `after_post_id` より小さいIDを持つ `Post` オブジェクトのリスト
+pub fn list_posts_after(conn: &mut DbConnection, after_post_id: Option<i32>, limit: i64) -> QueryResult<Vec<models::Post>> {
+    let mut query = schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .into_boxed();
+    if let Some(after_post_id) = after_post_id {
+        query = query.filter(schema::posts::post_id.lt(after_post_id));
+    }
+
+    query
+        .order(schema::posts::post_id.desc())
+        .limit(pagination::clamp_limit(limit))
+        .load(conn)
+}
+
+/// 複数のメールアドレスからユーザー名を一括で取得する関数
+///
+/// メールアドレスは `get_user_by_email` と同様に大文字・小文字を区別せずに比較します。
+/// 同じメールアドレスのユーザーが複数いる場合は `user_id` が最も小さいユーザーの名前を使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `emails`: 検索するメールアドレスのリスト
+///
+/// # 戻り値
+/// - 引数で渡されたメールアドレスをキー、ユーザー名を値とするマップ
+/// - 見つからなかったメールアドレスは含まれない
+pub fn name_map_by_emails(conn: &mut DbConnection, emails: &[&str]) -> QueryResult<HashMap<String, String>> {
+    if emails.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let lowered: Vec<String> = emails.iter().map(|email| email.to_lowercase()).collect();
+    let rows = schema::users::table
+        .filter(sql::lower(schema::users::email).eq_any(&lowered))
+        .select((schema::users::email, schema::users::name))
+        .order(schema::users::user_id.asc())
+        .load::<(String, String)>(conn)?;
+
+    let mut names_by_email: HashMap<String, String> = HashMap::new();
+    for (email, name) in rows {
+        names_by_email.entry(email.to_lowercase()).or_insert(name);
+    }
+
+    Ok(emails
+        .iter()
+        .zip(lowered)
+        .filter_map(|(email, key)| names_by_email.get(&key).map(|name| (email.to_string(), name.clone())))
+        .collect())
+}
+
+/// 投稿者ごとの最新の投稿を取得する関数
+///
+/// 公開済みの投稿が1件以上あるユーザーと、そのユーザーの最新の公開済み投稿を取得します。
+/// 公開日時に達していない予約投稿は、一覧と「より新しい投稿」のどちらの判定でも数えません。
+/// 作成日時が同じ投稿が複数ある場合は投稿IDが大きいものを最新とします。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+///
+/// # 戻り値
+/// - 投稿者の `PublicUser` と最新の `Post` の組のリスト(投稿の新しい順)
+pub fn latest_post_per_author(conn: &mut DbConnection) -> QueryResult<Vec<(models::PublicUser, models::Post)>> {
+    use diesel::dsl::{exists, not};
+
+    let newer_posts = diesel::alias!(schema::posts as newer_posts);
+    let newer_publish_at = newer_posts.field(schema::posts::publish_at);
+
+    schema::posts::table
+        .inner_join(schema::users::table)
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::users::active.eq(true))
+        .filter(publish_time_reached())
+        .filter(not(exists(
+            newer_posts
+                .filter(newer_posts.field(schema::posts::user_id).eq(schema::posts::user_id))
+                .filter(newer_posts.field(schema::posts::published).eq(true))
+                .filter(newer_publish_at.is_null().or(newer_publish_at.le(publish_at_now())))
+                .filter(
+                    newer_posts.field(schema::posts::created_at).gt(schema::posts::created_at).or(
+                        newer_posts.field(schema::posts::created_at).eq(schema::posts::created_at)
+                            .and(newer_posts.field(schema::posts::post_id).gt(schema::posts::post_id)),
+                    ),
+                ),
+        )))
+        .select((models::PublicUser::as_select(), schema::posts::all_columns))
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .load(conn)
+}
+
+/// フォロー中のユーザーがいいねした投稿を取得する関数
+///
+/// おすすめ表示用に、指定されたユーザーがフォローしているユーザーがいいねした公開済みの投稿を取得します。
+/// いいねしたフォロー中のユーザーの人数が多い順に並べ、同数の場合は新しい投稿を先にします。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: おすすめを表示するユーザーのID
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - `Post` といいねしたフォロー中のユーザーの人数の組のリスト
+pub fn get_posts_liked_by_following(conn: &mut DbConnection, user_id: i32, limit: i64) -> QueryResult<Vec<(models::Post, i64)>> {
+    let following = schema::follows::table
+        .filter(schema::follows::follower_id.eq(user_id))
+        .select(schema::follows::followee_id);
+
+    schema::post_likes::table
+        .inner_join(schema::posts::table)
+        .filter(schema::post_likes::user_id.eq_any(following))
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .group_by(schema::posts::post_id)
+        .select((schema::posts::all_columns, diesel::dsl::count_star()))
+        .order((
+            diesel::dsl::count_star().desc(),
+            schema::posts::created_at.desc(),
+            schema::posts::post_id.desc(),
+        ))
+        .limit(pagination::clamp_limit(limit))
+        .load(conn)
+}
+
+/// コメントとコメントが付いた投稿を取得する関数
+///
+/// モデレーションの際に、コメントとあわせて投稿のタイトルなどを表示するために使用します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 取得するコメントのID
+///
+/// # 戻り値
+/// - `Comment` と `Post` の組
+/// - コメントが存在しない場合は `NotFound` エラー
+pub fn get_comment_with_post(conn: &mut DbConnection, comment_id: i32) -> QueryResult<(models::Comment, models::Post)> {
+    schema::comments::table
+        .inner_join(schema::posts::table)
+        .filter(schema::comments::comment_id.eq(comment_id))
+        .select((schema::comments::all_columns, schema::posts::all_columns))
+        .first(conn)
+}
+
+/// 閲覧中のユーザーがいいね済みかどうかとあわせて公開済みの投稿を取得する関数
+///
+/// フィードで「いいね済み」を表示するため、各投稿に閲覧中のユーザーのいいねがあるかを1回のクエリで求めます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `viewer_user_id`: 閲覧中のユーザーのID
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` と閲覧中のユーザーがいいね済みかどうかの組のリスト(新しい順)
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn list_posts_with_like_status(conn: &mut DbConnection, viewer_user_id: i32, limit: i64, offset: i64) -> Result<Vec<(models::Post, bool)>, BrogError> {
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .left_join(
+            schema::post_likes::table.on(schema::post_likes::post_id
+                .eq(schema::posts::post_id)
+                .and(schema::post_likes::user_id.eq(viewer_user_id))),
+        )
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .select((schema::posts::all_columns, schema::post_likes::user_id.nullable().is_not_null()))
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// コメントへの返信の数を数える関数
+///
+/// 「N件の返信」の表示用に、指定されたコメントへの直接の返信の数を数えます。
+/// 返信への返信は含みません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 返信先のコメントのID
+///
+/// # 戻り値
+/// - 直接の返信の数
+pub fn count_replies(conn: &mut DbConnection, comment_id: i32) -> QueryResult<i64> {
+    schema::comments::table
+        .filter(schema::comments::parent_comment_id.eq(comment_id))
+        .count()
+        .get_result(conn)
+}
+
+/// 指定した期間に登録したユーザーを取得する関数
+///
+/// 管理画面での絞り込み用に、`from` から `to` までの期間(両端を含む)に登録したユーザーを登録の古い順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `from`: 期間の開始日
+/// - `to`: 期間の終了日
+///
+/// # 戻り値
+/// - `PublicUser` オブジェクトのリスト
+pub fn get_users_registered_between(conn: &mut DbConnection, from: NaiveDate, to: NaiveDate) -> QueryResult<Vec<models::PublicUser>> {
+    let start = from.and_hms_opt(0, 0, 0).expect("midnight is always valid");
+    let end = to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).expect("midnight is always valid");
+
+    schema::users::table
+        .filter(schema::users::created_at.ge(start))
+        .filter(schema::users::created_at.lt(end))
+        .select(models::PublicUser::as_select())
+        .order((schema::users::created_at.asc(), schema::users::user_id.asc()))
+        .load(conn)
+}
+
+/// 投稿詳細を読み込む関数
+///
+/// 投稿詳細ページの表示に必要な投稿、作成者、コメント数、いいね数、閲覧中のユーザーがいいね済みかどうかを取得します。
+/// 件数などはサブクエリで求め、1回のクエリで取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 取得する投稿のID
+/// - `viewer`: 閲覧中のユーザーのID。ログインしていない場合は `None`
+///
+/// # 戻り値
+/// - 組み立てられた `PostDetail` オブジェクト
+/// - 投稿が存在しない場合は `NotFound` エラー
+pub fn load_post_detail(conn: &mut DbConnection, post_id: i32, viewer: Option<i32>) -> QueryResult<models::PostDetail> {
+    let comment_count = schema::comments::table
+        .filter(schema::comments::post_id.eq(schema::posts::post_id))
+        .count()
+        .single_value();
+    let like_count = schema::post_likes::table
+        .filter(schema::post_likes::post_id.eq(schema::posts::post_id))
+        .count()
+        .single_value();
+    // viewer が None の場合は NULL との比較となり一致する行がないため、常に false になる
+    let viewer_liked = diesel::dsl::exists(
+        schema::post_likes::table
+            .filter(schema::post_likes::post_id.eq(schema::posts::post_id))
+            .filter(schema::post_likes::user_id.nullable().eq(viewer)),
+    );
+
+    let (post, author, comment_count, like_count, viewer_liked) = schema::posts::table
+        .inner_join(schema::users::table)
+        .filter(schema::posts::post_id.eq(post_id))
+        .select((
+            schema::posts::all_columns,
+            models::PublicUser::as_select(),
+            comment_count,
+            like_count,
+            viewer_liked,
+        ))
+        .first::<(models::Post, models::PublicUser, Option<i64>, Option<i64>, bool)>(conn)?;
+
+    Ok(models::PostDetail {
+        post,
+        author,
+        comment_count: comment_count.unwrap_or(0),
+        like_count: like_count.unwrap_or(0),
+        viewer_liked,
+    })
+}
+
+/// 古い空の下書きを削除する関数
+///
+/// 定期的な整理処理用に、`cutoff` より前に作成された未公開の投稿のうち、本文が空または空白のみのものを削除します。
+/// 空白の判定は `find_posts_with_empty_body` と同じです。
+/// 下書きに付いたコメント(返信とメンションを含む)、いいね、タグ付けも同じトランザクションで削除します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `cutoff`: この日時より前に作成された下書きを削除する
+///
+/// # 戻り値
+/// - 削除された投稿の数
+pub fn delete_empty_drafts_older_than(conn: &mut DbConnection, cutoff: NaiveDateTime) -> QueryResult<usize> {
+    // TRIM() はスペースしか取り除かないため、先にタブと改行を取り除く
+    let without_tabs_and_newlines = sql::replace(
+        sql::replace(sql::replace(schema::posts::post_body, "\t", ""), "\n", ""),
+        "\r",
+        "",
+    );
+
+    conn.transaction(|conn| {
+        let post_ids = schema::posts::table
+            .filter(schema::posts::published.eq(false))
+            .filter(schema::posts::created_at.lt(cutoff))
+            .filter(sql::trim(without_tabs_and_newlines).eq(""))
+            .select(schema::posts::post_id)
+            .load::<i32>(conn)?;
+        if post_ids.is_empty() {
+            return Ok(0);
+        }
+
+        delete_post_dependents(conn, &post_ids)?;
+        diesel::delete(schema::posts::table.filter(schema::posts::post_id.eq_any(&post_ids))).execute(conn)
+    })
+}
+
+/// ユーザーの投稿に付いた新しいコメントの数を数える関数
+///
+/// 「前回の訪問以降にN件の新しいコメント」の表示用に、ユーザーの投稿に `since` より後に付いたコメントの数を数えます。
+/// ユーザー自身が書いたコメントは数えません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `author_user_id`: 投稿を作成したユーザーのID
+/// - `since`: この日時より後のコメントを数える
+///
+/// # 戻り値
+/// - コメント数
+pub fn count_comments_on_user_posts_since(conn: &mut DbConnection, author_user_id: i32, since: NaiveDateTime) -> QueryResult<i64> {
+    schema::comments::table
+        .inner_join(schema::posts::table)
+        .filter(schema::posts::user_id.eq(author_user_id))
+        .filter(schema::comments::user_id.ne(author_user_id))
+        .filter(schema::comments::created_at.gt(since))
+        .count()
+        .get_result(conn)
+}
+
+/// 関連度の順に投稿を検索する関数
+///
+/// `search_posts` と同じ条件で検索し、タイトルに含まれる場合は2点、本文に含まれる場合は1点として合計した関連度の高い順に並べます。
+/// 関連度が同じ場合は新しい投稿を先にします。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `query`: 検索する文字列
+///
+/// # 戻り値
+/// - 見つかった `Post` と関連度の組のリスト
+pub fn search_posts_ranked(conn: &mut DbConnection, query: &str) -> QueryResult<Vec<(models::Post, i32)>> {
+    use diesel::dsl::case_when;
+    use diesel::sql_types::Integer;
+
+    let pattern = format!("%{}%", sql::escape_like(&query.to_lowercase()));
+    let title_matches = sql::lower(schema::posts::title).like(pattern.clone());
+    let body_matches = sql::lower(schema::posts::post_body).like(pattern);
+    let score = case_when::<_, _, Integer>(title_matches.clone(), 2).otherwise(0)
+        + case_when::<_, _, Integer>(body_matches.clone(), 1).otherwise(0);
+
+    schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .filter(title_matches.or(body_matches))
+        .select((schema::posts::all_columns, score.clone()))
+        .order((score.desc(), schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .load(conn)
+}
+
+/// コメント通知の内容を取得する関数
+///
+/// 投稿の作成者に新しいコメントを通知するため、コメント、コメント作成者の名前、投稿のタイトルを1回のクエリで取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `comment_id`: 通知するコメントのID
+///
+/// # 戻り値
+/// - 組み立てられた `CommentNotification` オブジェクト
+/// - コメントが存在しない場合は `NotFound` エラー
+pub fn notification_context(conn: &mut DbConnection, comment_id: i32) -> QueryResult<models::CommentNotification> {
+    let (comment, commenter_name, post_title) = schema::comments::table
+        .inner_join(schema::users::table)
+        .inner_join(schema::posts::table)
+        .filter(schema::comments::comment_id.eq(comment_id))
+        .select((schema::comments::all_columns, schema::users::name, schema::posts::title))
+        .first::<(models::Comment, String, String)>(conn)?;
+
+    Ok(models::CommentNotification {
+        comment,
+        commenter_name,
+        post_title,
+    })
+}
+
+/// 投稿の2つの版の差分を求める関数
+///
+/// `rev_a_id` の版を古い版、`rev_b_id` の版を新しい版として、本文を行ごとに比較します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `rev_a_id`: 比較元の版のID
+/// - `rev_b_id`: 比較先の版のID
+///
+/// # 戻り値
+/// - 差分の行 `DiffLine` のリスト
+/// - 版が存在しない場合は `BrogError::NotFound`
+/// - 2つの版が別の投稿のものである場合は `BrogError::Validation`
+pub fn diff_post_revisions(conn: &mut DbConnection, rev_a_id: i32, rev_b_id: i32) -> Result<Vec<models::DiffLine>, BrogError> {
+    let rev_a = schema::post_revisions::table.find(rev_a_id).first::<models::PostRevision>(conn)?;
+    let rev_b = schema::post_revisions::table.find(rev_b_id).first::<models::PostRevision>(conn)?;
+    if rev_a.post_id() != rev_b.post_id() {
+        return Err(BrogError::Validation("revisions belong to different posts".to_string()));
+    }
+
+    let diff = similar::TextDiff::from_lines(rev_a.post_body(), rev_b.post_body());
+    let lines = diff
+        .iter_all_changes()
+        .map(|change| {
+            let line = change.value().trim_end_matches(['\r', '\n']).to_string();
+            match change.tag() {
+                similar::ChangeTag::Equal => models::DiffLine::Unchanged(line),
+                similar::ChangeTag::Insert => models::DiffLine::Added(line),
+                similar::ChangeTag::Delete => models::DiffLine::Removed(line),
+            }
+        })
+        .collect();
+    Ok(lines)
+}
+
+/// ユーザーの投稿をタイトルで取得する関数
+///
+/// インポートの際に同じタイトルの投稿を飛ばすために使用します。
+/// タイトルは前後のスペースを取り除いたうえで完全一致で比較します。大文字・小文字も区別します。
+/// 同じタイトルの投稿が複数ある場合は最も古い投稿を返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 投稿を作成したユーザーのID
+/// - `title`: 検索するタイトル
+///
+/// # 戻り値
+/// - 見つかった場合は `Some(Post)`、存在しない場合は `None`
+pub fn find_post_by_title_for_user(conn: &mut DbConnection, user_id: i32, title: &str) -> QueryResult<Option<models::Post>> {
+    let title = title.trim();
+
+    // MySQLの照合順序では大文字・小文字を区別せずに比較されるため、候補を取得してから完全一致で絞り込む
+    let candidates = schema::posts::table
+        .filter(schema::posts::user_id.eq(user_id))
+        .filter(sql::trim(schema::posts::title).eq(title))
+        .order(schema::posts::post_id.asc())
+        .load::<models::Post>(conn)?;
+
+    // SQLの TRIM() と同じく前後のスペースのみを取り除いて比較する
+    Ok(candidates.into_iter().find(|post| post.title().trim_matches(' ') == title))
+}
+
+/// ダイジェストメールの送信先を取得する関数
+///
+/// 週ごとのまとめを送るため、`since` 以降にコメントしたユーザーを重複なく取得します。
+/// 無効化されたユーザーと、通知を無効にしているユーザーは含みません。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `since`: この日時以降のコメントを対象にする
+///
+/// # 戻り値
+/// - `PublicUser` オブジェクトのリスト(ユーザーIDの昇順)
+pub fn get_digest_recipients(conn: &mut DbConnection, since: NaiveDateTime) -> QueryResult<Vec<models::PublicUser>> {
+    let recent_commenters = schema::comments::table
+        .filter(schema::comments::created_at.ge(since))
+        .select(schema::comments::user_id);
+
+    schema::users::table
+        .filter(schema::users::user_id.eq_any(recent_commenters))
+        .filter(schema::users::active.eq(true))
+        .filter(schema::users::notifications_enabled.eq(true))
+        .select(models::PublicUser::as_select())
+        .order(schema::users::user_id.asc())
+        .load(conn)
+}
+
+/// 乱数を返すSQL式
+#[cfg(not(feature = "postgres"))]
+const RANDOM_SQL: &str = "RAND()";
+#[cfg(feature = "postgres")]
+const RANDOM_SQL: &str = "RANDOM()";
+
+/// 公開済みの投稿を無作為に取得する関数
+///
+/// 「ランダムな投稿」ボタン用に、公開済みの投稿を無作為に最大 `count` 件取得します。
+/// `ORDER BY RAND()` は対象のすべての行に乱数を振ってから並べ替えるため、投稿数が多くなると遅くなります。
+/// 投稿が数十万件を超えるような場合は、IDの範囲から乱数で選ぶなど別の方法を検討してください。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `count`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+///
+/// # 戻り値
+/// - 重複のない `Post` オブジェクトのリスト
+pub fn get_random_published_posts(conn: &mut DbConnection, count: i64) -> QueryResult<Vec<models::Post>> {
+    schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .order(diesel::dsl::sql::<diesel::sql_types::Double>(RANDOM_SQL))
+        .limit(pagination::clamp_limit(count))
+        .load(conn)
+}
+
+/// プロフィールヘッダーを取得する関数
+///
+/// プロフィールページの上部に表示する名前、表示名、公開済みの投稿数、コメント数、フォロワー数、登録日時を取得します。
+/// 件数はサブクエリで求め、1回のクエリで取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 対象のユーザーのID
+///
+/// # 戻り値
+/// - 組み立てられた `ProfileHeader` オブジェクト
+/// - ユーザーが存在しない場合は `NotFound` エラー
+pub fn get_profile_header(conn: &mut DbConnection, user_id: i32) -> QueryResult<models::ProfileHeader> {
+    let post_count = schema::posts::table
+        .filter(schema::posts::user_id.eq(schema::users::user_id))
+        .filter(schema::posts::published.eq(true))
+        .count()
+        .single_value();
+    let comment_count = schema::comments::table
+        .filter(schema::comments::user_id.eq(schema::users::user_id))
+        .count()
+        .single_value();
+    let follower_count = schema::follows::table
+        .filter(schema::follows::followee_id.eq(schema::users::user_id))
+        .count()
+        .single_value();
+
+    let (name, display_name, joined_at, post_count, comment_count, follower_count) = schema::users::table
+        .find(user_id)
+        .select((
+            schema::users::name,
+            schema::users::display_name,
+            schema::users::created_at,
+            post_count,
+            comment_count,
+            follower_count,
+        ))
+        .first::<(String, Option<String>, NaiveDateTime, Option<i64>, Option<i64>, Option<i64>)>(conn)?;
+
+    Ok(models::ProfileHeader {
+        user_id,
+        name,
+        display_name,
+        post_count: post_count.unwrap_or(0),
+        comment_count: comment_count.unwrap_or(0),
+        follower_count: follower_count.unwrap_or(0),
+        joined_at,
+    })
+}
+
+/// 複数のユーザーの投稿を取得する関数
+///
+/// チームブログなど共有のフィード用に、指定されたユーザーたちの公開済みの投稿を新しい順に取得します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_ids`: 投稿を取得するユーザーのIDのリスト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` オブジェクトのリスト
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn get_posts_by_users(conn: &mut DbConnection, user_ids: &[i32], limit: i64, offset: i64) -> Result<Vec<models::Post>, BrogError> {
+    pagination::validate_offset(offset)?;
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let posts = schema::posts::table
+        .filter(schema::posts::user_id.eq_any(user_ids))
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load(conn)?;
+    Ok(posts)
+}
+
+/// 古い投稿をまとめて非公開にする関数
+///
+/// アーカイブの方針に従い、`cutoff` より前に作成された公開済みの投稿を非公開にします。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `cutoff`: この日時より前に作成された投稿を非公開にする
+///
+/// # 戻り値
+/// - 非公開にした投稿の数
+pub fn unpublish_posts_older_than(conn: &mut DbConnection, cutoff: NaiveDateTime) -> QueryResult<usize> {
+    diesel::update(
+        schema::posts::table
+            .filter(schema::posts::published.eq(true))
+            .filter(schema::posts::created_at.lt(cutoff)),
+    )
+    .set(schema::posts::published.eq(false))
+    .execute(conn)
+}
+
+/// 投稿のコメント一覧でのコメントの位置を取得する関数
+///
+/// コメントへのリンクから該当するページを開くため、`build_post_view` と同じ古い順の一覧で何番目にあるかを求めます。
+/// 呼び出し側で位置を1ページの件数で割ってページ番号を求めてください。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: コメントが付いた投稿のID
+/// - `comment_id`: 位置を求めるコメントのID
+///
+/// # 戻り値
+/// - 0から始まるコメントの位置
+/// - コメントが存在しないか、指定された投稿のコメントでない場合は `BrogError::NotFound`
+pub fn get_comment_position(conn: &mut DbConnection, post_id: i32, comment_id: i32) -> Result<i64, BrogError> {
+    let on_post = diesel::select(diesel::dsl::exists(
+        schema::comments::table
+            .find(comment_id)
+            .filter(schema::comments::post_id.eq(post_id)),
+    ))
+    .get_result::<bool>(conn)?;
+    if !on_post {
+        return Err(BrogError::NotFound);
+    }
+
+    let position = schema::comments::table
+        .filter(schema::comments::post_id.eq(post_id))
+        .filter(schema::comments::comment_id.lt(comment_id))
+        .count()
+        .get_result(conn)?;
+    Ok(position)
+}
+
+/// 最初のコメントとあわせて公開済みの投稿を取得する関数
+///
+/// フィードでコメントをプレビュー表示するため、各投稿に付いた最も古いコメントを取得します。
+/// 投稿の取得と、それらの投稿の最初のコメントの取得の2回のクエリで行います。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `limit`: 取得する件数(`pagination::MAX_PAGE_SIZE` までに丸める)
+/// - `offset`: 取得を開始する位置
+///
+/// # 戻り値
+/// - `Post` と最初のコメントの組のリスト(新しい順)。コメントがない投稿は `None`
+/// - `offset` が負の場合は `BrogError::Validation`
+pub fn list_posts_with_top_comment(conn: &mut DbConnection, limit: i64, offset: i64) -> Result<Vec<(models::Post, Option<models::Comment>)>, BrogError> {
+    use diesel::dsl::{exists, not};
+
+    pagination::validate_offset(offset)?;
+
+    let posts = schema::posts::table
+        .filter(schema::posts::published.eq(true))
+        .filter(schema::posts::user_id.eq_any(active_user_ids()))
+        .filter(publish_time_reached())
+        .order((schema::posts::created_at.desc(), schema::posts::post_id.desc()))
+        .limit(pagination::clamp_limit(limit))
+        .offset(offset)
+        .load::<models::Post>(conn)?;
+    if posts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let post_ids: Vec<i32> = posts.iter().map(|post| post.post_id()).collect();
+    let older_comments = diesel::alias!(schema::comments as older_comments);
+    let mut first_comments: HashMap<i32, models::Comment> = schema::comments::table
+        .filter(schema::comments::post_id.eq_any(&post_ids))
+        .filter(not(exists(
+            older_comments
+                .filter(older_comments.field(schema::comments::post_id).eq(schema::comments::post_id))
+                .filter(older_comments.field(schema::comments::comment_id).lt(schema::comments::comment_id)),
+        )))
+        .load::<models::Comment>(conn)?
+        .into_iter()
+        .map(|comment| (comment.post_id(), comment))
+        .collect();
+
+    Ok(posts
+        .into_iter()
+        .map(|post| {
+            let comment = first_comments.remove(&post.post_id());
+            (post, comment)
+        })
+        .collect())
+}
+
+/// アバター画像のURLの最大文字数
+const MAX_AVATAR_URL_CHARS: usize = 255;
+
+/// アバター画像のURLを設定する関数
+///
+/// `url` に `None` を渡すとアバター画像を解除します。
+/// URLは `http://` または `https://` で始まり、空白を含まないものに限ります。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 対象のユーザーのID
+/// - `url`: アバター画像のURL
+///
+/// # 戻り値
+/// - 更新された行数
+/// - URLが不正な場合は `BrogError::Validation`
+pub fn set_avatar_url(conn: &mut DbConnection, user_id: i32, url: Option<&str>) -> Result<usize, BrogError> {
+    if let Some(url) = url {
+        validate_avatar_url(url)?;
+    }
+
+    let affected_rows = diesel::update(schema::users::table.find(user_id))
+        .set(schema::users::avatar_url.eq(url))
+        .execute(conn)?;
+    Ok(affected_rows)
+}
+
+/// アバター画像のURLを検証する関数
+fn validate_avatar_url(url: &str) -> Result<(), BrogError> {
+    let lower = url.to_lowercase();
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+        .ok_or_else(|| BrogError::Validation("avatar url must start with http:// or https://".to_string()))?;
+    if rest.is_empty() || url.chars().any(char::is_whitespace) {
+        return Err(BrogError::Validation("avatar url is invalid".to_string()));
+    }
+    if url.chars().count() > MAX_AVATAR_URL_CHARS {
+        return Err(BrogError::Validation(format!(
+            "avatar url must be at most {} characters",
+            MAX_AVATAR_URL_CHARS
+        )));
+    }
+    Ok(())
+}
+
+/// タグが付いた投稿のIDの一覧を取得する関数
+///
+/// キャッシュの作成など、投稿IDだけが必要な場合に `Post` を読み込まずに済ませるために使用します。
+/// 公開ステータスにかかわらず、タグが付いたすべての投稿のIDを返します。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `tag_id`: タグのID
+///
+/// # 戻り値
+/// - 投稿IDのリスト(昇順)
+pub fn get_post_ids_by_tag(conn: &mut DbConnection, tag_id: i32) -> QueryResult<Vec<i32>> {
+    schema::post_tags::table
+        .filter(schema::post_tags::tag_id.eq(tag_id))
+        .select(schema::post_tags::post_id)
+        .order(schema::post_tags::post_id.asc())
+        .load(conn)
+}
+
+/// 複数の投稿のコメントをまとめて取得する関数
+///
+/// 複数の投稿をコメント付きで表示する際に投稿ごとにクエリを発行しなくて済むよう、1回のクエリで取得して投稿IDごとにまとめます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_ids`: コメントを取得する投稿のIDのリスト
+///
+/// # 戻り値
+/// - 投稿IDをキー、その投稿のコメント(古い順)を値とするマップ
+/// - コメントがない投稿も空のリストとして含む
+pub fn get_comments_for_posts(conn: &mut DbConnection, post_ids: &[i32]) -> QueryResult<HashMap<i32, Vec<models::Comment>>> {
+    let mut comments_by_post: HashMap<i32, Vec<models::Comment>> =
+        post_ids.iter().map(|&post_id| (post_id, Vec::new())).collect();
+    if post_ids.is_empty() {
+        return Ok(comments_by_post);
+    }
+
+    let comments = schema::comments::table
+        .filter(schema::comments::post_id.eq_any(post_ids))
+        .order(schema::comments::comment_id.asc())
+        .load::<models::Comment>(conn)?;
+    for comment in comments {
+        comments_by_post.entry(comment.post_id()).or_default().push(comment);
+    }
+    Ok(comments_by_post)
+}
+
+/// 投稿の公開を予約する関数
+///
+/// `publish_at` に日時を指定すると投稿を非公開にし、その日時に `publish_due_posts` で公開されるようにします。
+/// `None` を指定すると予約を取り消します(公開ステータスは変更しません)。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `post_id`: 対象の投稿のID
+/// - `publish_at`: 公開する日時
+///
+/// # 戻り値
+/// - 更新された行数
+pub fn schedule_post(conn: &mut DbConnection, post_id: i32, publish_at: Option<NaiveDateTime>) -> QueryResult<usize> {
+    match publish_at {
+        Some(publish_at) => diesel::update(schema::posts::table.find(post_id))
+            .set((schema::posts::publish_at.eq(publish_at), schema::posts::published.eq(false)))
+            .execute(conn),
+        None => diesel::update(schema::posts::table.find(post_id))
+            .set(schema::posts::publish_at.eq(None::<NaiveDateTime>))
+            .execute(conn),
+    }
+}
+
+/// 公開日時に達した予約投稿を公開する関数
+///
+/// 定期的に実行する処理用に、`publish_at` が `now` 以前の未公開の投稿を公開します。
+/// 公開した投稿の `publish_at` は `NULL` に戻すため、後で非公開にした投稿が再び公開されることはありません。
+/// タイトルまたは本文が空の投稿は公開せず、`publish_at` も残したままにします(内容を補えば次回の実行で公開されます)。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `now`: 現在日時
+///
+/// # 戻り値
+/// - 公開した投稿の数
+pub fn publish_due_posts(conn: &mut DbConnection, now: NaiveDateTime) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let due_posts = schema::posts::table
+            .filter(schema::posts::published.eq(false))
+            .filter(schema::posts::publish_at.le(now))
+            .select((schema::posts::post_id, schema::posts::title, schema::posts::post_body))
+            .for_update()
+            .load::<(i32, String, String)>(conn)?;
+        let publishable: Vec<i32> = due_posts
+            .into_iter()
+            .filter(|(_, title, body)| models::validate_publishable(title, body).is_ok())
+            .map(|(post_id, _, _)| post_id)
+            .collect();
+        if publishable.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::update(schema::posts::table.filter(schema::posts::post_id.eq_any(&publishable)))
+            .set((schema::posts::published.eq(true), schema::posts::publish_at.eq(None::<NaiveDateTime>)))
+            .execute(conn)
+    })
+}
+
+/// ユーザーの下書きの数を数える関数
+///
+/// 投稿者のダッシュボードに表示するため、ユーザーの未公開の投稿の数を数えます。
+/// 公開を予約している投稿も下書きとして数えます。
+///
+/// # 引数
+/// - `conn`: データベース接続用のDbConnectionオブジェクト
+/// - `user_id`: 投稿を作成したユーザーのID
+///
+/// # 戻り値
+/// - 下書きの数
+pub fn count_drafts_by_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<i64> {
+    schema::posts::table
+        .filter(schema::posts::user_id.eq(user_id))
+        .filter(schema::posts::published.eq(false))
+        .count()
+        .get_result(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comment_body_at_limit_is_accepted() {
+        assert!(validate_comment_body(&"a".repeat(MAX_COMMENT_CHARS)).is_ok());
+        // 文字数はバイト数ではなく文字単位で数える
+        assert!(validate_comment_body(&"あ".repeat(MAX_COMMENT_CHARS)).is_ok());
+    }
+
+    #[test]
+    fn comment_body_over_limit_is_rejected() {
+        assert!(matches!(validate_comment_body(&"a".repeat(MAX_COMMENT_CHARS + 1)), Err(BrogError::Validation(_))));
+        assert!(matches!(validate_comment_body(&"あ".repeat(MAX_COMMENT_CHARS + 1)), Err(BrogError::Validation(_))));
+    }
+
+    #[test]
+    fn extract_mentions_finds_names_after_at_sign() {
+        assert_eq!(extract_mentions("thanks @alice and @bob_2, cc @carol-x."), vec!["alice", "bob_2", "carol-x"]);
+        assert!(extract_mentions("no mentions here").is_empty());
+    }
+
+    #[test]
+    fn extract_mentions_skips_email_addresses_and_duplicates() {
+        assert_eq!(extract_mentions("mail alice@example.com or ping @alice"), vec!["alice"]);
+        assert_eq!(extract_mentions("@bob @bob @bob"), vec!["bob"]);
+        assert!(extract_mentions("@ alone").is_empty());
+    }
+
+    #[test]
+    fn normalize_tag_name_trims_and_lowercases() {
+        assert_eq!(normalize_tag_name("  Rust "), "rust");
+        assert_eq!(normalize_tag_name("rust"), normalize_tag_name("RUST"));
+    }
+
+    #[test]
+    fn link_heavy_comment_looks_like_spam() {
+        assert!(looks_like_spam("buy http://a.example https://b.example www.c.example http://d.example"));
+        assert!(!looks_like_spam("see https://a.example and https://b.example for details"));
+    }
+
+    #[test]
+    fn scheme_and_www_count_as_one_link() {
+        assert!(!looks_like_spam("see https://www.a.example and https://www.b.example for details"));
+        assert!(looks_like_spam("https://www.a.example https://www.b.example www.c.example http://www.d.example"));
+    }
+
+    #[test]
+    fn all_caps_comment_looks_like_spam() {
+        assert!(looks_like_spam("FREE MONEY CLICK HERE RIGHT NOW"));
+        // 短い大文字の語だけでは判定しない
+        assert!(!looks_like_spam("OK, LGTM"));
+    }
+
+    #[test]
+    fn repeated_characters_look_like_spam() {
+        assert!(looks_like_spam("wow!!!!!!!!!!"));
+        assert!(!looks_like_spam("wow!!!"));
+    }
+
+    #[test]
+    fn normal_comment_does_not_look_like_spam() {
+        assert!(!looks_like_spam("Thanks for the write-up, the section on lifetimes helped a lot."));
+        assert!(!looks_like_spam("素晴らしい記事でした。ありがとうございます!"));
+    }
+}
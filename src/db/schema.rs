@@ -1,11 +1,66 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    comment_mentions (comment_id, mentioned_user_id) {
+        comment_id -> Integer,
+        mentioned_user_id -> Integer,
+    }
+}
+
 diesel::table! {
     comments (comment_id) {
         comment_id -> Integer,
         post_id -> Integer,
         user_id -> Integer,
         comment_body -> Text,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+        parent_comment_id -> Nullable<Integer>,
+        edited -> Bool,
+        edit_count -> Integer,
+        flagged -> Bool,
+    }
+}
+
+diesel::table! {
+    follows (follow_id) {
+        follow_id -> Integer,
+        follower_id -> Integer,
+        followee_id -> Integer,
+    }
+}
+
+diesel::table! {
+    password_resets (token) {
+        #[max_length = 64]
+        token -> Varchar,
+        user_id -> Integer,
+        expires_at -> Datetime,
+        used -> Bool,
+    }
+}
+
+diesel::table! {
+    post_likes (user_id, post_id) {
+        user_id -> Integer,
+        post_id -> Integer,
+        created_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    post_revisions (revision_id) {
+        revision_id -> Integer,
+        post_id -> Integer,
+        post_body -> Text,
+        created_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    post_tags (post_id, tag_id) {
+        post_id -> Integer,
+        tag_id -> Integer,
     }
 }
 
@@ -17,6 +72,24 @@ diesel::table! {
         post_body -> Text,
         published -> Bool,
         user_id -> Integer,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+        #[max_length = 255]
+        slug -> Nullable<Varchar>,
+        view_count -> Integer,
+        sort_order -> Integer,
+        version -> Integer,
+        comments_enabled -> Bool,
+        needs_review -> Bool,
+        publish_at -> Nullable<Datetime>,
+    }
+}
+
+diesel::table! {
+    tags (tag_id) {
+        tag_id -> Integer,
+        #[max_length = 255]
+        name -> Varchar,
     }
 }
 
@@ -29,15 +102,40 @@ diesel::table! {
         email -> Varchar,
         #[max_length = 255]
         password_hash -> Varchar,
+        is_admin -> Bool,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+        notifications_enabled -> Bool,
+        last_active_at -> Nullable<Datetime>,
+        active -> Bool,
+        #[max_length = 255]
+        display_name -> Nullable<Varchar>,
+        #[max_length = 255]
+        avatar_url -> Nullable<Varchar>,
     }
 }
 
+diesel::joinable!(comment_mentions -> comments (comment_id));
+diesel::joinable!(comment_mentions -> users (mentioned_user_id));
 diesel::joinable!(comments -> posts (post_id));
 diesel::joinable!(comments -> users (user_id));
+diesel::joinable!(password_resets -> users (user_id));
+diesel::joinable!(post_likes -> posts (post_id));
+diesel::joinable!(post_likes -> users (user_id));
+diesel::joinable!(post_revisions -> posts (post_id));
+diesel::joinable!(post_tags -> posts (post_id));
+diesel::joinable!(post_tags -> tags (tag_id));
 diesel::joinable!(posts -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    comment_mentions,
     comments,
+    follows,
+    password_resets,
+    post_likes,
+    post_revisions,
+    post_tags,
     posts,
+    tags,
     users,
 );
@@ -0,0 +1,36 @@
+//! SQL関数モジュール
+//!
+//! Dieselのクエリビルダで使用するSQL関数を定義します。
+//! ここで定義した関数はMySQLとPostgreSQLのどちらでも同じ名前で使用できるものに限ります。
+
+use diesel::sql_types::Text;
+
+diesel::define_sql_function! {
+    /// 文字列を小文字に変換する `LOWER()`
+    /// 大文字・小文字を区別せずに比較する際に、比較する両辺に使用する。
+    fn lower(x: Text) -> Text;
+}
+
+diesel::define_sql_function! {
+    /// 文字列の前後の空白を取り除く `TRIM()`
+    fn trim(x: Text) -> Text;
+}
+
+diesel::define_sql_function! {
+    /// 文字列中の `from` をすべて `to` に置き換える `REPLACE()`
+    fn replace(x: Text, from: Text, to: Text) -> Text;
+}
+
+/// `LIKE` のパターンで特別な意味を持つ文字をエスケープする関数
+///
+/// `%`、`_`、`\` の前に `\` を付け、入力をそのままの文字列として検索できるようにします。
+pub fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
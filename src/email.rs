@@ -0,0 +1,94 @@
+//! メールアドレスモジュール
+//!
+//! 検証済みのメールアドレスを表す型を定義します。
+//! 入力を受け取った時点で `Email` に変換しておくことで、不正な形式のメールアドレスがデータベース操作まで渡らないようにします。
+
+use std::fmt;
+
+use crate::error::BrogError;
+
+/// メールアドレス
+///
+/// 形式を検証し、小文字に揃えたメールアドレスを保持する型。
+/// `TryFrom<&str>` でのみ作成できる。
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Email(String);
+
+impl Email {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 文字列を検証してメールアドレスに変換する
+///
+/// 前後の空白を取り除いて小文字にしたうえで、次の条件を満たすかを確認する。
+/// - `@` がちょうど1つあり、その前後が空でない
+/// - ドメインに `.` が含まれ、`.` で始まったり終わったりしない
+/// - 空白を含まない
+///
+/// 条件を満たさない場合は `BrogError::Validation` を返す。
+impl TryFrom<&str> for Email {
+    type Error = BrogError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let email = value.trim().to_lowercase();
+        let invalid = || BrogError::Validation("email is invalid".to_string());
+
+        if email.chars().any(char::is_whitespace) {
+            return Err(invalid());
+        }
+        let (local, domain) = email.split_once('@').ok_or_else(invalid)?;
+        if local.is_empty() || domain.contains('@') {
+            return Err(invalid());
+        }
+        if !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.') {
+            return Err(invalid());
+        }
+
+        Ok(Email(email))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_address_is_accepted() {
+        let email = Email::try_from("alice@example.com").unwrap();
+        assert_eq!(email.as_str(), "alice@example.com");
+        assert_eq!(email.to_string(), "alice@example.com");
+    }
+
+    #[test]
+    fn mixed_case_address_is_lowercased_and_trimmed() {
+        let email = Email::try_from("  Alice.Smith@Example.COM ").unwrap();
+        assert_eq!(email.as_str(), "alice.smith@example.com");
+        assert_eq!(email, Email::try_from("alice.smith@example.com").unwrap());
+    }
+
+    #[test]
+    fn invalid_addresses_are_rejected() {
+        for input in [
+            "",
+            "alice",
+            "@example.com",
+            "alice@",
+            "alice@@example.com",
+            "alice@example@com",
+            "alice@localhost",
+            "alice@.example.com",
+            "alice@example.com.",
+            "alice smith@example.com",
+        ] {
+            assert!(matches!(Email::try_from(input), Err(BrogError::Validation(_))), "{:?} should be rejected", input);
+        }
+    }
+}
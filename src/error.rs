@@ -0,0 +1,168 @@
+//! エラーモジュール
+//!
+//! アプリケーション固有のエラーを定義します。
+//! Dieselのエラー(`diesel::result::Error`)だけでは表現できない、業務ルール違反などを扱うために使用します。
+//! Dieselのエラーは `From` を実装しているため `?` 演算子でそのまま `BrogError` に変換できます。
+
+use std::fmt;
+
+/// アプリケーション全体で使用するエラー型
+#[derive(Debug)]
+pub enum BrogError {
+    /// 対象のレコードが見つからない
+    NotFound,
+    /// 入力値が不正
+    Validation(String),
+    /// 操作する権限がない
+    Forbidden,
+    /// 既存のデータと競合している
+    Conflict,
+    /// ユーザー名が既に使用されている
+    DuplicateName,
+    /// 投稿がコメントを受け付けていない
+    CommentsClosed,
+    /// パスワードのハッシュ化・照合でのエラー
+    PasswordHash(bcrypt::BcryptError),
+    /// データベース操作でのエラー
+    Database(diesel::result::Error),
+    /// マイグレーションの適用でのエラー
+    Migration(Box<dyn std::error::Error + Send + Sync>),
+    /// コネクションプールから接続を取り出せない
+    Pool(diesel::r2d2::PoolError),
+}
+
+impl BrogError {
+    /// エラーに対応するHTTPステータスコードの目安
+    ///
+    /// Webハンドラでエラーをレスポンスに変換する際に、変換方法を揃えるために使用します。
+    /// 一意制約違反によるデータベースのエラーは既存のデータとの競合として 409 を返します。
+    /// メールアドレスの重複には専用のバリアントを設けていません。
+    /// 一意制約で重複を防いでいる場合は `Database(UniqueViolation)` として返るため、ほかの競合と同じく 409 になります。
+    pub fn status_hint(&self) -> u16 {
+        match self {
+            BrogError::NotFound => 404,
+            BrogError::Validation(_) => 400,
+            BrogError::Forbidden | BrogError::CommentsClosed => 403,
+            BrogError::Conflict | BrogError::DuplicateName => 409,
+            BrogError::Database(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            )) => 409,
+            BrogError::PasswordHash(_) | BrogError::Database(_) | BrogError::Migration(_) => 500,
+            BrogError::Pool(_) => 503,
+        }
+    }
+}
+
+impl fmt::Display for BrogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrogError::NotFound => write!(f, "record not found"),
+            BrogError::Validation(message) => write!(f, "validation error: {}", message),
+            BrogError::Forbidden => write!(f, "operation not permitted"),
+            BrogError::Conflict => write!(f, "conflict with existing data"),
+            BrogError::DuplicateName => write!(f, "user name is already taken"),
+            BrogError::CommentsClosed => write!(f, "comments are closed for this post"),
+            BrogError::PasswordHash(e) => write!(f, "password hash error: {}", e),
+            BrogError::Database(e) => write!(f, "database error: {}", e),
+            BrogError::Migration(e) => write!(f, "migration error: {}", e),
+            BrogError::Pool(e) => write!(f, "connection pool error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BrogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BrogError::PasswordHash(e) => Some(e),
+            BrogError::Database(e) => Some(e),
+            BrogError::Migration(e) => Some(e.as_ref()),
+            BrogError::Pool(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Dieselのエラーを変換する
+/// レコードが存在しない場合は `NotFound`、それ以外は `Database` として扱う。
+impl From<diesel::result::Error> for BrogError {
+    fn from(e: diesel::result::Error) -> Self {
+        match e {
+            diesel::result::Error::NotFound => BrogError::NotFound,
+            e => BrogError::Database(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::{ManageConnection, Pool};
+    use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+    /// 常に接続に失敗するコネクションマネージャー
+    struct FailingManager;
+
+    impl ManageConnection for FailingManager {
+        type Connection = ();
+        type Error = std::io::Error;
+
+        fn connect(&self) -> Result<(), Self::Error> {
+            Err(std::io::Error::other("connection refused"))
+        }
+
+        fn is_valid(&self, _: &mut ()) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn has_broken(&self, _: &mut ()) -> bool {
+            false
+        }
+    }
+
+    fn database_error(kind: DatabaseErrorKind) -> BrogError {
+        BrogError::Database(DieselError::DatabaseError(kind, Box::new("constraint failed".to_string())))
+    }
+
+    #[test]
+    fn client_errors_map_to_4xx() {
+        assert_eq!(BrogError::NotFound.status_hint(), 404);
+        assert_eq!(BrogError::Validation("invalid".to_string()).status_hint(), 400);
+        assert_eq!(BrogError::Forbidden.status_hint(), 403);
+        assert_eq!(BrogError::CommentsClosed.status_hint(), 403);
+        assert_eq!(BrogError::Conflict.status_hint(), 409);
+        assert_eq!(BrogError::DuplicateName.status_hint(), 409);
+    }
+
+    #[test]
+    fn unique_violation_maps_to_conflict() {
+        assert_eq!(database_error(DatabaseErrorKind::UniqueViolation).status_hint(), 409);
+    }
+
+    #[test]
+    fn other_database_errors_map_to_500() {
+        assert_eq!(database_error(DatabaseErrorKind::ForeignKeyViolation).status_hint(), 500);
+        assert_eq!(BrogError::Database(DieselError::RollbackTransaction).status_hint(), 500);
+    }
+
+    #[test]
+    fn internal_errors_map_to_500() {
+        assert_eq!(BrogError::PasswordHash(bcrypt::BcryptError::InvalidCost("99".to_string())).status_hint(), 500);
+        assert_eq!(BrogError::Migration("migration failed".into()).status_hint(), 500);
+    }
+
+    #[test]
+    fn pool_error_maps_to_503() {
+        let pool = Pool::builder()
+            .connection_timeout(std::time::Duration::from_millis(10))
+            .build_unchecked(FailingManager);
+        let error = BrogError::Pool(pool.get().expect_err("connection should fail"));
+        assert_eq!(error.status_hint(), 503);
+    }
+
+    #[test]
+    fn diesel_not_found_converts_to_not_found() {
+        assert!(matches!(BrogError::from(DieselError::NotFound), BrogError::NotFound));
+        assert!(matches!(BrogError::from(DieselError::RollbackTransaction), BrogError::Database(_)));
+    }
+}
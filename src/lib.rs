@@ -1,3 +1,7 @@
 pub mod db;
+pub mod email;
+pub mod error;
 pub mod models;
-
+pub mod password;
+pub mod requests;
+pub mod service;
@@ -1,17 +1,20 @@
-/// モデルモジュール
-/// 
-/// アプリケーションで使用するデータモデルを定義します。
-/// モデルは構造体で定義し、データベースとやり取りするためにDieselのトレイトを実装します。
-/// さらに、必要に応じてゲッターメソッドを提供し、フィールドにアクセスできるようにします。
-///
-/// 使用するフレームワークとライブラリ：
-/// - diesel: データベースと接続し、ORMとして動作するため。
-/// - serde: モデルの構造体をJSON形式にシリアライズ・デシリアライズする場合に使用可能です。　拡張用
-///
-/// それぞれの構造体はschema.rsのテーブルと紐付けされDB操作に使用する。
+//! モデルモジュール
+//! 
+//! アプリケーションで使用するデータモデルを定義します。
+//! モデルは構造体で定義し、データベースとやり取りするためにDieselのトレイトを実装します。
+//! さらに、必要に応じてゲッターメソッドを提供し、フィールドにアクセスできるようにします。
+//!
+//! 使用するフレームワークとライブラリ：
+//! - diesel: データベースと接続し、ORMとして動作するため。
+//! - serde: モデルの構造体をJSON形式にシリアライズ・デシリアライズする場合に使用可能です。　拡張用
+//!
+//! それぞれの構造体はschema.rsのテーブルと紐付けされDB操作に使用する。
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use crate::db::schema::*;
+use crate::error::BrogError;
+use crate::password::PasswordHash;
 
 /// ユーザーモデル
 /// ユーザーに関する情報を保持
@@ -22,7 +25,15 @@ pub struct User {
     user_id: i32,
     name: String,
     email: String,
-    password_hash: String,
+    password_hash: PasswordHash,
+    is_admin: bool,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    notifications_enabled: bool,
+    last_active_at: Option<NaiveDateTime>,
+    active: bool,
+    display_name: Option<String>,
+    avatar_url: Option<String>,
 }
 
 /// 外部からUser構造体へのアクセスを制限するためにゲッターメソッドを定義
@@ -40,9 +51,74 @@ impl User {
         &self.email
     }
 
-    pub fn password_hash(&self) -> &str {
+    pub fn password_hash(&self) -> &PasswordHash {
         &self.password_hash
     }
+
+    pub fn is_admin(&self) -> bool {
+        self.is_admin
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+
+    pub fn notifications_enabled(&self) -> bool {
+        self.notifications_enabled
+    }
+
+    /// 最後に活動した日時
+    /// 一度も活動していない場合は `None`
+    pub fn last_active_at(&self) -> Option<NaiveDateTime> {
+        self.last_active_at
+    }
+
+    /// アカウントが有効かどうか
+    /// 無効化されたユーザーの投稿は公開の一覧に表示されない。
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// 表示名
+    /// 設定されていない場合は `None` で、その場合は `name` を表示する。
+    pub fn display_name(&self) -> Option<&str> {
+        self.display_name.as_deref()
+    }
+
+    /// アバター画像のURL
+    pub fn avatar_url(&self) -> Option<&str> {
+        self.avatar_url.as_deref()
+    }
+}
+
+/// 公開ユーザーモデル
+/// 他のユーザーに表示してよいユーザー情報のみを保持する
+/// メールアドレスやパスワードハッシュを含まないため、一覧表示などで `User` の代わりに使用する。
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = users)]
+pub struct PublicUser {
+    user_id: i32,
+    name: String,
+    avatar_url: Option<String>,
+}
+
+impl PublicUser {
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// アバター画像のURL
+    pub fn avatar_url(&self) -> Option<&str> {
+        self.avatar_url.as_deref()
+    }
 }
 
 /// 投稿モデル
@@ -59,6 +135,15 @@ pub struct Post {
     post_body: String,
     published: bool,
     user_id: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    slug: Option<String>,
+    view_count: i32,
+    sort_order: i32,
+    version: i32,
+    comments_enabled: bool,
+    needs_review: bool,
+    publish_at: Option<NaiveDateTime>,
 }
 
 /// 外部からPost構造体へのアクセスを制限するためにゲッターメソッドを定義
@@ -82,6 +167,115 @@ impl Post {
     pub fn user_id(&self) -> i32 {
         self.user_id
     }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+
+    /// URLに使用するスラッグ
+    /// 投稿の作成時に設定されるため、通常は `None` にならない。
+    pub fn slug(&self) -> Option<&str> {
+        self.slug.as_deref()
+    }
+
+    pub fn view_count(&self) -> i32 {
+        self.view_count
+    }
+
+    /// 手動で並べ替える際の表示順
+    pub fn sort_order(&self) -> i32 {
+        self.sort_order
+    }
+
+    /// 楽観的排他制御に使用するバージョン番号
+    /// 投稿が更新されるたびに1ずつ増える。
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// コメントを受け付けているかどうか
+    pub fn comments_enabled(&self) -> bool {
+        self.comments_enabled
+    }
+
+    /// モデレーターの確認待ちかどうか
+    pub fn needs_review(&self) -> bool {
+        self.needs_review
+    }
+
+    /// 予約投稿の公開日時
+    /// 予約されていない場合は `None`
+    pub fn publish_at(&self) -> Option<NaiveDateTime> {
+        self.publish_at
+    }
+
+    /// 本文の抜粋を作成する
+    ///
+    /// 本文が `max_chars` 文字を超える場合、`max_chars` 文字以内で最後の空白の位置で切り詰めて末尾に「…」を付ける。
+    /// 空白が見つからない場合(日本語の文章など)は `max_chars` 文字目で切り詰める。
+    /// 文字単位で数えるため、マルチバイト文字の途中で切れることはない。
+    pub fn excerpt(&self, max_chars: usize) -> String {
+        if self.post_body.chars().count() <= max_chars {
+            return self.post_body.clone();
+        }
+
+        // max_chars文字目のバイト位置を求める
+        let cut = self
+            .post_body
+            .char_indices()
+            .nth(max_chars)
+            .map(|(index, _)| index)
+            .unwrap_or(self.post_body.len());
+        let head = &self.post_body[..cut];
+
+        // 単語の途中で切れないよう最後の空白で切る(切る位置の直後が空白なら単語は途切れていない)
+        let at_boundary = self.post_body[cut..].starts_with(char::is_whitespace);
+        let head = match head.rfind(char::is_whitespace) {
+            Some(index) if index > 0 && !at_boundary => &head[..index],
+            _ => head,
+        };
+
+        format!("{}…", head.trim_end())
+    }
+
+    /// 本文を読むのにかかるおおよその時間(分)を計算する
+    ///
+    /// 英語などの空白で区切られた文章は1分あたり `WORDS_PER_MINUTE` 語、
+    /// 日本語などのCJK文字は1分あたり `CJK_CHARS_PER_MINUTE` 文字として計算し、切り上げる。
+    /// 本文が空でも最低1分を返す。
+    pub fn reading_time_minutes(&self) -> u32 {
+        let cjk_chars = self.post_body.chars().filter(|c| is_cjk(*c)).count();
+        let words = self
+            .post_body
+            .split_whitespace()
+            .filter(|word| word.chars().any(|c| c.is_alphanumeric() && !is_cjk(c)))
+            .count();
+
+        let minutes = words as f64 / WORDS_PER_MINUTE + cjk_chars as f64 / CJK_CHARS_PER_MINUTE;
+        (minutes.ceil() as u32).max(1)
+    }
+}
+
+/// 読了時間の計算に使用する1分あたりの単語数
+const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// 読了時間の計算に使用する1分あたりのCJK文字数
+const CJK_CHARS_PER_MINUTE: f64 = 500.0;
+
+/// CJK文字(ひらがな、カタカナ、漢字、ハングル)かどうかを判定する
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // ひらがな・カタカナ
+        | '\u{3400}'..='\u{4DBF}' // CJK統合漢字拡張A
+        | '\u{4E00}'..='\u{9FFF}' // CJK統合漢字
+        | '\u{AC00}'..='\u{D7AF}' // ハングル
+        | '\u{F900}'..='\u{FAFF}' // CJK互換漢字
+        | '\u{FF66}'..='\u{FF9F}' // 半角カタカナ
+    )
 }
 
 /// コメントモデル
@@ -98,6 +292,12 @@ pub struct Comment {
     post_id: i32,
     user_id: i32,
     comment_body: String,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    parent_comment_id: Option<i32>,
+    edited: bool,
+    edit_count: i32,
+    flagged: bool,
 }
 
 impl Comment {
@@ -116,6 +316,326 @@ impl Comment {
     pub fn comment_body(&self) -> &str {
         &self.comment_body
     }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+
+    /// 返信先のコメントのID
+    /// 投稿への直接のコメントの場合は `None`
+    pub fn parent_comment_id(&self) -> Option<i32> {
+        self.parent_comment_id
+    }
+
+    /// 作成後に本文が変更されたかどうか
+    pub fn edited(&self) -> bool {
+        self.edited
+    }
+
+    /// 本文が変更された回数
+    pub fn edit_count(&self) -> i32 {
+        self.edit_count
+    }
+
+    /// スパムの疑いがあるとして自動でフラグが付けられたかどうか
+    pub fn flagged(&self) -> bool {
+        self.flagged
+    }
+}
+
+/// メンションモデル
+/// コメント中の `@ユーザー名` によるユーザーへのメンションを保持する
+/// 通知の送信先を求めるために使用する。
+
+#[derive(Identifiable, Queryable, Associations)]
+#[belongs_to(Comment)]
+#[diesel(table_name = comment_mentions)]
+#[primary_key(comment_id, mentioned_user_id)]
+pub struct CommentMention {
+    comment_id: i32,
+    mentioned_user_id: i32,
+}
+
+impl CommentMention {
+    pub fn comment_id(&self) -> i32 {
+        self.comment_id
+    }
+
+    pub fn mentioned_user_id(&self) -> i32 {
+        self.mentioned_user_id
+    }
+}
+
+/// フォローモデル
+/// ユーザー間のフォロー関係を保持する
+/// `follower_id` のユーザーが `followee_id` のユーザーをフォローしていることを表す。
+/// 同じ組み合わせのフォローはデータベースの一意制約により1件のみとなる。
+
+#[derive(Identifiable, Queryable)]
+#[diesel(table_name = follows)]
+#[primary_key(follow_id)]
+pub struct Follow {
+    follow_id: i32,
+    follower_id: i32,
+    followee_id: i32,
+}
+
+impl Follow {
+    pub fn follow_id(&self) -> i32 {
+        self.follow_id
+    }
+
+    pub fn follower_id(&self) -> i32 {
+        self.follower_id
+    }
+
+    pub fn followee_id(&self) -> i32 {
+        self.followee_id
+    }
+}
+
+/// パスワードリセットモデル
+/// パスワード再設定用のトークンを保持する
+/// トークンは有効期限 `expires_at` までに1度だけ使用できる。
+/// `token` 列には発行したトークンそのものではなく、SHA-256ハッシュ(16進数)を保存する。
+
+#[derive(Identifiable, Queryable, Associations)]
+#[belongs_to(User)]
+#[diesel(table_name = password_resets)]
+#[primary_key(token)]
+pub struct PasswordReset {
+    token: String,
+    user_id: i32,
+    expires_at: NaiveDateTime,
+    used: bool,
+}
+
+impl PasswordReset {
+    /// トークンのSHA-256ハッシュ
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    pub fn expires_at(&self) -> NaiveDateTime {
+        self.expires_at
+    }
+
+    pub fn used(&self) -> bool {
+        self.used
+    }
+}
+
+/// いいねモデル
+/// ユーザーが投稿に付けた「いいね」を保持する
+/// ユーザーIDと投稿IDの組が主キーとなるため、同じ投稿へのいいねは1ユーザーにつき1件のみとなる。
+
+#[derive(Identifiable, Queryable, Associations)]
+#[belongs_to(User)]
+#[belongs_to(Post)]
+#[diesel(table_name = post_likes)]
+#[primary_key(user_id, post_id)]
+pub struct PostLike {
+    user_id: i32,
+    post_id: i32,
+    created_at: NaiveDateTime,
+}
+
+impl PostLike {
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    pub fn post_id(&self) -> i32 {
+        self.post_id
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+}
+
+/// 投稿の版モデル
+/// 投稿の本文が変更されるたびに、その時点の本文を保持する
+/// 版同士の差分を表示するために使用する。
+
+#[derive(Identifiable, Queryable, Associations)]
+#[belongs_to(Post)]
+#[diesel(table_name = post_revisions)]
+#[primary_key(revision_id)]
+pub struct PostRevision {
+    revision_id: i32,
+    post_id: i32,
+    post_body: String,
+    created_at: NaiveDateTime,
+}
+
+impl PostRevision {
+    pub fn revision_id(&self) -> i32 {
+        self.revision_id
+    }
+
+    pub fn post_id(&self) -> i32 {
+        self.post_id
+    }
+
+    pub fn post_body(&self) -> &str {
+        &self.post_body
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+}
+
+/// タグモデル
+/// 投稿を分類するためのタグを保持する
+/// タグ名はデータベースの一意制約により重複しない。
+
+#[derive(Identifiable, Queryable)]
+#[diesel(table_name = tags)]
+#[primary_key(tag_id)]
+pub struct Tag {
+    tag_id: i32,
+    name: String,
+}
+
+impl Tag {
+    pub fn tag_id(&self) -> i32 {
+        self.tag_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// 投稿タグモデル
+/// 投稿とタグの関連付けを保持する
+
+#[derive(Identifiable, Queryable, Associations)]
+#[belongs_to(Post)]
+#[belongs_to(Tag)]
+#[diesel(table_name = post_tags)]
+#[primary_key(post_id, tag_id)]
+pub struct PostTag {
+    post_id: i32,
+    tag_id: i32,
+}
+
+impl PostTag {
+    pub fn post_id(&self) -> i32 {
+        self.post_id
+    }
+
+    pub fn tag_id(&self) -> i32 {
+        self.tag_id
+    }
+}
+
+/// 投稿ビュー
+/// 投稿詳細の表示に必要なデータをまとめて保持する
+/// 投稿とその作成者、投稿に付いたコメントとそれぞれのコメント作成者を持つ。
+pub struct PostView {
+    pub post: Post,
+    pub author: User,
+    pub comments: Vec<CommentView>,
+}
+
+/// コメントビュー
+/// コメントとそのコメントの作成者を保持する
+pub struct CommentView {
+    pub comment: Comment,
+    pub author: User,
+}
+
+/// コメントツリーのノード
+/// コメントと、そのコメントへの返信を保持する
+pub struct CommentNode {
+    pub comment: Comment,
+    pub children: Vec<CommentNode>,
+}
+
+/// 投稿の統計情報
+/// 投稿のフッターに表示するいいね数、コメント数、閲覧数をまとめて保持する
+pub struct PostStats {
+    pub likes: i64,
+    pub comments: i64,
+    pub views: i64,
+}
+
+/// 投稿詳細
+/// 投稿詳細ページの表示に必要なデータを1回の読み込みでまとめて保持する
+/// `viewer_liked` は閲覧中のユーザーがいいね済みかどうかで、ログインしていない場合は常に `false` となる。
+pub struct PostDetail {
+    pub post: Post,
+    pub author: PublicUser,
+    pub comment_count: i64,
+    pub like_count: i64,
+    pub viewer_liked: bool,
+}
+
+/// コメント通知の内容
+/// 投稿の作成者に新しいコメントを通知する際に必要な、コメント、コメント作成者の名前、投稿のタイトルを保持する
+pub struct CommentNotification {
+    pub comment: Comment,
+    pub commenter_name: String,
+    pub post_title: String,
+}
+
+/// プロフィールヘッダー
+/// プロフィールページの上部に表示するユーザー情報と各種の件数をまとめて保持する
+pub struct ProfileHeader {
+    pub user_id: i32,
+    pub name: String,
+    pub display_name: Option<String>,
+    pub post_count: i64,
+    pub comment_count: i64,
+    pub follower_count: i64,
+    pub joined_at: NaiveDateTime,
+}
+
+/// ユーザー削除の影響
+/// ユーザーを削除した場合に合わせて削除される投稿数とコメント数を保持する
+pub struct DeletionImpact {
+    pub posts: i64,
+    pub comments: i64,
+}
+
+/// タイムラインの項目
+/// プロフィールのタイムラインに表示する、ユーザーの投稿またはコメントを保持する
+pub enum TimelineItem {
+    Post(Post),
+    Comment(Comment),
+}
+
+impl TimelineItem {
+    /// 項目が作成された日時
+    pub fn created_at(&self) -> NaiveDateTime {
+        match self {
+            TimelineItem::Post(post) => post.created_at(),
+            TimelineItem::Comment(comment) => comment.created_at(),
+        }
+    }
+}
+
+/// 差分の行
+/// 2つの版の本文を行ごとに比較した結果の1行を保持する
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    /// 両方の版に含まれる行
+    Unchanged(String),
+    /// 新しい版で追加された行
+    Added(String),
+    /// 新しい版で削除された行
+    Removed(String),
 }
 
 /// 以下の構造体はそれぞれのモデルにデータを挿入する際に使用する。
@@ -126,7 +646,7 @@ impl Comment {
 pub struct NewUser {
     pub name: String,
     pub email: String,
-    pub password_hash: String,
+    pub password_hash: PasswordHash,
 }
 
 #[derive(Insertable, Queryable)]
@@ -138,10 +658,246 @@ pub struct NewPost {
     pub user_id: i32,
 }
 
+impl NewPost {
+    /// `NewPost` のビルダーを作成する
+    /// `published` は指定しない場合 `false`(下書き)となる。
+    pub fn builder(user_id: i32) -> NewPostBuilder {
+        NewPostBuilder {
+            user_id,
+            title: None,
+            body: None,
+            published: false,
+        }
+    }
+}
+
+/// `NewPost` を組み立てるためのビルダー
+/// タイトルと本文は必須で、`build` の際に設定されているかを検証する。
+pub struct NewPostBuilder {
+    user_id: i32,
+    title: Option<String>,
+    body: Option<String>,
+    published: bool,
+}
+
+impl NewPostBuilder {
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self
+    }
+
+    pub fn published(mut self, published: bool) -> Self {
+        self.published = published;
+        self
+    }
+
+    /// `NewPost` を作成する
+    /// タイトルが未設定または空白のみの場合、本文が未設定の場合は `BrogError::Validation` を返す。
+    pub fn build(self) -> Result<NewPost, BrogError> {
+        let title = match self.title {
+            Some(title) if !title.trim().is_empty() => title,
+            _ => return Err(BrogError::Validation("title is required".to_string())),
+        };
+        let post_body = self
+            .body
+            .ok_or_else(|| BrogError::Validation("body is required".to_string()))?;
+        // 下書きは本文が空でもよいが、公開する投稿はタイトルと本文がそろっている必要がある
+        if self.published {
+            validate_publishable(&title, &post_body)?;
+        }
+
+        Ok(NewPost {
+            title,
+            post_body,
+            published: self.published,
+            user_id: self.user_id,
+        })
+    }
+}
+
+/// 投稿を公開できるかを検証する関数
+///
+/// 下書きは書きかけでも保存できますが、公開する投稿にはタイトルと本文の両方が必要です。
+/// 投稿を公開するすべての処理(`NewPostBuilder::build` と `queries` の各関数)はこの関数で検証します。
+/// 空白(スペース、タブ、改行)のみの場合も空とみなします。
+///
+/// # 引数
+/// - `title`: 投稿のタイトル
+/// - `body`: 投稿の本文
+///
+/// # 戻り値
+/// - タイトルまたは本文が空の場合は `BrogError::Validation`
+pub fn validate_publishable(title: &str, body: &str) -> Result<(), BrogError> {
+    if title.trim().is_empty() {
+        return Err(BrogError::Validation("published post must have a title".to_string()));
+    }
+    if body.trim().is_empty() {
+        return Err(BrogError::Validation("published post must have a body".to_string()));
+    }
+    Ok(())
+}
+
 #[derive(Insertable, Queryable)]
 #[diesel(table_name = comments)]
 pub struct NewComment {
     pub user_id: i32,
     pub post_id: i32,
     pub comment_body: String,
-}
\ No newline at end of file
+    pub parent_comment_id: Option<i32>,
+    pub flagged: bool,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = comment_mentions)]
+pub struct NewCommentMention {
+    pub comment_id: i32,
+    pub mentioned_user_id: i32,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = follows)]
+pub struct NewFollow {
+    pub follower_id: i32,
+    pub followee_id: i32,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = password_resets)]
+pub struct NewPasswordReset {
+    pub token: String,
+    pub user_id: i32,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = post_likes)]
+pub struct NewPostLike {
+    pub user_id: i32,
+    pub post_id: i32,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = tags)]
+pub struct NewTag {
+    pub name: String,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = post_revisions)]
+pub struct NewPostRevision {
+    pub post_id: i32,
+    pub post_body: String,
+}
+
+#[derive(Insertable, Queryable)]
+#[diesel(table_name = post_tags)]
+pub struct NewPostTag {
+    pub post_id: i32,
+    pub tag_id: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post_with_body(body: &str) -> Post {
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        Post {
+            post_id: 1,
+            title: "title".to_string(),
+            post_body: body.to_string(),
+            published: true,
+            user_id: 1,
+            created_at: now,
+            updated_at: now,
+            slug: None,
+            view_count: 0,
+            sort_order: 0,
+            version: 1,
+            comments_enabled: true,
+            needs_review: false,
+            publish_at: None,
+        }
+    }
+
+    #[test]
+    fn excerpt_keeps_short_body() {
+        let post = post_with_body("A short post.");
+        assert_eq!(post.excerpt(20), "A short post.");
+        assert_eq!(post.excerpt(13), "A short post.");
+    }
+
+    #[test]
+    fn excerpt_cuts_long_body_at_word_boundary() {
+        let post = post_with_body("The quick brown fox jumps over the lazy dog");
+        assert_eq!(post.excerpt(12), "The quick…");
+        assert_eq!(post.excerpt(15), "The quick brown…");
+    }
+
+    #[test]
+    fn excerpt_cuts_multibyte_body_by_chars() {
+        let post = post_with_body("吾輩は猫である。名前はまだ無い。");
+        assert_eq!(post.excerpt(8), "吾輩は猫である。…");
+        assert_eq!(post.excerpt(3), "吾輩は…");
+    }
+
+    #[test]
+    fn reading_time_of_short_post_is_one_minute() {
+        assert_eq!(post_with_body("").reading_time_minutes(), 1);
+        assert_eq!(post_with_body("Just a few words here.").reading_time_minutes(), 1);
+        assert_eq!(post_with_body("短い投稿です。").reading_time_minutes(), 1);
+    }
+
+    #[test]
+    fn reading_time_of_long_post_rounds_up() {
+        let words = vec!["word"; 450].join(" ");
+        assert_eq!(post_with_body(&words).reading_time_minutes(), 3);
+
+        let japanese = "あ".repeat(1200);
+        assert_eq!(post_with_body(&japanese).reading_time_minutes(), 3);
+    }
+
+    #[test]
+    fn builder_creates_draft_by_default() {
+        let post = NewPost::builder(7).title("Draft").body("").build().unwrap();
+        assert_eq!(post.title, "Draft");
+        assert_eq!(post.post_body, "");
+        assert!(!post.published);
+        assert_eq!(post.user_id, 7);
+    }
+
+    #[test]
+    fn builder_requires_title() {
+        assert!(matches!(NewPost::builder(7).body("body").build(), Err(BrogError::Validation(_))));
+        assert!(matches!(NewPost::builder(7).title("  ").body("body").build(), Err(BrogError::Validation(_))));
+    }
+
+    #[test]
+    fn builder_requires_body() {
+        assert!(matches!(NewPost::builder(7).title("Draft").build(), Err(BrogError::Validation(_))));
+    }
+
+    #[test]
+    fn builder_rejects_publishing_without_body() {
+        let result = NewPost::builder(7).title("Title").body(" \n").published(true).build();
+        assert!(matches!(result, Err(BrogError::Validation(_))));
+    }
+
+    #[test]
+    fn builder_accepts_publishing_complete_post() {
+        let post = NewPost::builder(7).title("Title").body("body").published(true).build().unwrap();
+        assert!(post.published);
+    }
+
+    #[test]
+    fn validate_publishable_requires_title_and_body() {
+        assert!(validate_publishable("Title", "body").is_ok());
+        assert!(matches!(validate_publishable(" \t", "body"), Err(BrogError::Validation(_))));
+        assert!(matches!(validate_publishable("Title", "\r\n"), Err(BrogError::Validation(_))));
+    }
+}
@@ -0,0 +1,110 @@
+//! パスワードモジュール
+//!
+//! パスワードのハッシュ化と照合を行います。
+//! データベースには平文のパスワードを保存せず、このモジュールで作成したハッシュのみを保存します。
+//!
+//! 使用するフレームワークとライブラリ：
+//! - bcrypt: パスワードのハッシュ化と照合のため
+
+use std::fmt;
+
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+
+use crate::error::BrogError;
+
+/// パスワードハッシュ
+///
+/// ハッシュ化済みのパスワードを保持する型。
+/// ログなどに誤って出力されないよう `Debug` では値を伏せて表示し、`Serialize` も実装しない。
+#[derive(Clone, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct PasswordHash(String);
+
+impl PasswordHash {
+    /// ハッシュ化済みの文字列から作成する
+    /// 平文のパスワードを渡さないこと。平文からは `hash_password` で作成する。
+    pub fn new(hash: String) -> Self {
+        PasswordHash(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PasswordHash([REDACTED])")
+    }
+}
+
+impl<DB> FromSql<Text, DB> for PasswordHash
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        String::from_sql(bytes).map(PasswordHash)
+    }
+}
+
+impl<DB> ToSql<Text, DB> for PasswordHash
+where
+    DB: Backend,
+    String: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.0.to_sql(out)
+    }
+}
+
+/// 平文のパスワードをハッシュ化する関数
+///
+/// # 引数
+/// - `plaintext`: 平文のパスワード
+///
+/// # 戻り値
+/// - ハッシュ化されたパスワード
+pub fn hash_password(plaintext: &str) -> Result<PasswordHash, BrogError> {
+    bcrypt::hash(plaintext, bcrypt::DEFAULT_COST)
+        .map(PasswordHash)
+        .map_err(BrogError::PasswordHash)
+}
+
+/// 平文のパスワードとハッシュを照合する関数
+///
+/// # 引数
+/// - `plaintext`: 平文のパスワード
+/// - `password_hash`: 保存されているパスワードハッシュ
+///
+/// # 戻り値
+/// - 一致する場合は `true`
+pub fn verify_password(plaintext: &str, password_hash: &PasswordHash) -> Result<bool, BrogError> {
+    bcrypt::verify(plaintext, password_hash.as_str()).map_err(BrogError::PasswordHash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_hides_hash() {
+        let hash = PasswordHash::new("$2b$12$secrethashvalue".to_string());
+
+        let debug = format!("{:?}", hash);
+        assert_eq!(debug, "PasswordHash([REDACTED])");
+        assert!(!debug.contains("secrethashvalue"));
+        assert!(!format!("{:?}", Some(&hash)).contains("secrethashvalue"));
+    }
+
+    #[test]
+    fn as_str_returns_stored_hash() {
+        let hash = PasswordHash::new("$2b$12$secrethashvalue".to_string());
+
+        assert_eq!(hash.as_str(), "$2b$12$secrethashvalue");
+    }
+}
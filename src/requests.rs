@@ -0,0 +1,229 @@
+//! リクエストモジュール
+//!
+//! Webハンドラが受け取るJSONリクエストボディを定義します。
+//! それぞれの構造体は `validate` で入力値を検証し、クエリ関数に渡せる形に変換します。
+//! 検証内容はクエリ側の検証と揃えているため、ここで通った値はクエリ側でも拒否されません。
+//!
+//! 使用するフレームワークとライブラリ：
+//! - serde: JSONのリクエストボディを構造体にデシリアライズするため
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::queries;
+use crate::email::Email;
+use crate::error::BrogError;
+use crate::models::{NewComment, NewPost};
+
+/// パスワードの最小文字数
+pub const MIN_PASSWORD_CHARS: usize = 8;
+
+/// 投稿作成リクエスト
+/// `published` を省略した場合は下書きとして扱う。
+#[derive(Deserialize)]
+pub struct CreatePostRequest {
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub published: bool,
+}
+
+impl CreatePostRequest {
+    /// リクエストを検証して `NewPost` に変換する
+    ///
+    /// # 引数
+    /// - `user_id`: 投稿を作成するユーザーのID(認証情報から取得する)
+    pub fn validate(self, user_id: i32) -> Result<NewPost, BrogError> {
+        NewPost::builder(user_id)
+            .title(&self.title)
+            .body(&self.body)
+            .published(self.published)
+            .build()
+    }
+}
+
+/// コメント作成リクエスト
+#[derive(Deserialize)]
+pub struct CreateCommentRequest {
+    pub post_id: i32,
+    pub body: String,
+}
+
+impl CreateCommentRequest {
+    /// リクエストを検証して `NewComment` に変換する
+    ///
+    /// # 引数
+    /// - `user_id`: コメントを作成するユーザーのID(認証情報から取得する)
+    pub fn validate(self, user_id: i32) -> Result<NewComment, BrogError> {
+        queries::validate_comment_body(&self.body)?;
+
+        Ok(NewComment {
+            user_id,
+            post_id: self.post_id,
+            flagged: queries::looks_like_spam(&self.body),
+            comment_body: self.body,
+            parent_comment_id: None,
+        })
+    }
+}
+
+/// ユーザー登録リクエスト
+/// パスワードは平文で受け取り、`queries::register_user` でハッシュ化する。
+#[derive(Deserialize)]
+pub struct RegisterRequest {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+}
+
+impl RegisterRequest {
+    /// リクエストを検証し、検証済みのメールアドレスを返す
+    ///
+    /// `validate_new_user` で検証し、エラーがある場合はすべてのメッセージをまとめた `BrogError::Validation` を返す。
+    pub fn validate(&self) -> Result<Email, BrogError> {
+        validate_new_user(&self.name, &self.email, &self.password).map_err(|errors| {
+            let messages: Vec<String> = errors.into_iter().map(|error| error.message).collect();
+            BrogError::Validation(messages.join(", "))
+        })?;
+        Email::try_from(self.email.as_str())
+    }
+}
+
+/// 項目ごとの入力エラー
+/// フォームで項目の横にエラーを表示できるよう、どの項目のエラーかとメッセージを保持する。
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: &str) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// ユーザー登録の入力値を検証する
+///
+/// 最初のエラーで止めずにすべての項目を検証し、見つかったエラーをまとめて返す。
+/// 名前が空白のみ、メールアドレスが `Email` として不正な形式、パスワードが `MIN_PASSWORD_CHARS` 文字未満の場合をエラーとする。
+///
+/// # 引数
+/// - `name`: ユーザーの名前
+/// - `email`: メールアドレス
+/// - `password`: 平文のパスワード
+pub fn validate_new_user(name: &str, email: &str, password: &str) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name", "name is required"));
+    }
+    if Email::try_from(email).is_err() {
+        errors.push(FieldError::new("email", "email is invalid"));
+    }
+    if password.chars().count() < MIN_PASSWORD_CHARS {
+        errors.push(FieldError::new(
+            "password",
+            &format!("password must be at least {} characters", MIN_PASSWORD_CHARS),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_post_request_defaults_to_draft() {
+        let request: CreatePostRequest = serde_json::from_str(r#"{"title": "Hello", "body": "World"}"#).unwrap();
+        let post = request.validate(3).unwrap();
+        assert_eq!(post.title, "Hello");
+        assert_eq!(post.post_body, "World");
+        assert!(!post.published);
+        assert_eq!(post.user_id, 3);
+    }
+
+    #[test]
+    fn create_post_request_rejects_blank_title() {
+        let request: CreatePostRequest = serde_json::from_str(r#"{"title": " ", "body": "World", "published": true}"#).unwrap();
+        assert!(matches!(request.validate(3), Err(BrogError::Validation(_))));
+    }
+
+    #[test]
+    fn create_post_request_requires_fields() {
+        assert!(serde_json::from_str::<CreatePostRequest>(r#"{"title": "Hello"}"#).is_err());
+    }
+
+    #[test]
+    fn create_comment_request_validates_body() {
+        let request: CreateCommentRequest = serde_json::from_str(r#"{"post_id": 5, "body": "Nice post"}"#).unwrap();
+        let comment = request.validate(3).unwrap();
+        assert_eq!(comment.post_id, 5);
+        assert_eq!(comment.user_id, 3);
+        assert_eq!(comment.comment_body, "Nice post");
+        assert_eq!(comment.parent_comment_id, None);
+
+        let too_long = format!(r#"{{"post_id": 5, "body": "{}"}}"#, "a".repeat(queries::MAX_COMMENT_CHARS + 1));
+        let request: CreateCommentRequest = serde_json::from_str(&too_long).unwrap();
+        assert!(matches!(request.validate(3), Err(BrogError::Validation(_))));
+    }
+
+    #[test]
+    fn register_request_returns_normalized_email() {
+        let request: RegisterRequest =
+            serde_json::from_str(r#"{"name": "alice", "email": "Alice@Example.com", "password": "long-enough"}"#).unwrap();
+        assert_eq!(request.validate().unwrap().as_str(), "alice@example.com");
+    }
+
+    #[test]
+    fn register_request_rejects_short_password() {
+        let request: RegisterRequest =
+            serde_json::from_str(r#"{"name": "alice", "email": "alice@example.com", "password": "short"}"#).unwrap();
+        assert!(matches!(request.validate(), Err(BrogError::Validation(_))));
+    }
+
+    /// エラーになった項目の名前を取得する
+    fn failed_fields(name: &str, email: &str, password: &str) -> Vec<String> {
+        match validate_new_user(name, email, password) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|error| error.field).collect(),
+        }
+    }
+
+    #[test]
+    fn validate_new_user_accepts_valid_input() {
+        assert!(validate_new_user("alice", "alice@example.com", "long-enough").is_ok());
+    }
+
+    #[test]
+    fn validate_new_user_rejects_blank_name() {
+        assert_eq!(failed_fields(" ", "alice@example.com", "long-enough"), vec!["name"]);
+    }
+
+    #[test]
+    fn validate_new_user_rejects_invalid_email() {
+        assert_eq!(failed_fields("alice", "not-an-email", "long-enough"), vec!["email"]);
+    }
+
+    #[test]
+    fn validate_new_user_rejects_short_password() {
+        assert_eq!(failed_fields("alice", "alice@example.com", "short"), vec!["password"]);
+        assert!(failed_fields("alice", "alice@example.com", &"a".repeat(MIN_PASSWORD_CHARS)).is_empty());
+    }
+
+    #[test]
+    fn validate_new_user_returns_every_error() {
+        let errors = validate_new_user("", "not-an-email", "short").unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["name", "email", "password"]);
+        assert!(errors.iter().all(|error| !error.message.is_empty()));
+    }
+}
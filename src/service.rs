@@ -0,0 +1,118 @@
+//! サービスモジュール
+//!
+//! Webフレームワークのアプリケーション状態として共有するための `BrogService` を定義します。
+//! `BrogService` はコネクションプールを保持し、呼び出しごとに接続を取り出してクエリ関数を実行します。
+//!
+//! 使用するフレームワークとライブラリ：
+//! - diesel/r2d2: データベース接続のコネクションプールを管理するため
+
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+
+use crate::db::queries;
+use crate::db::DbConnection;
+use crate::email::Email;
+use crate::error::BrogError;
+use crate::models;
+
+/// データベース接続のコネクションプール
+pub type DbPool = Pool<ConnectionManager<DbConnection>>;
+
+/// アプリケーションのサービス
+///
+/// 内部のコネクションプールは参照カウントで共有されるため、`clone` してもプールは複製されない。
+/// actix-web の `web::Data` などに入れて各ハンドラから使用する。
+#[derive(Clone)]
+pub struct BrogService {
+    pool: DbPool,
+}
+
+impl BrogService {
+    /// 作成済みのコネクションプールからサービスを作成する
+    pub fn new(pool: DbPool) -> Self {
+        BrogService { pool }
+    }
+
+    /// データベースのURLからコネクションプールを作成し、サービスを作成する
+    ///
+    /// # 引数
+    /// - `database_url`: 接続先のデータベースのURL
+    ///
+    /// # 戻り値
+    /// - プールの作成に失敗した場合は `BrogError::Pool`
+    pub fn connect(database_url: &str) -> Result<Self, BrogError> {
+        let manager = ConnectionManager::<DbConnection>::new(database_url);
+        let pool = Pool::builder().build(manager).map_err(BrogError::Pool)?;
+        Ok(BrogService::new(pool))
+    }
+
+    /// プールから接続を取り出す
+    fn conn(&self) -> Result<PooledConnection<ConnectionManager<DbConnection>>, BrogError> {
+        self.pool.get().map_err(BrogError::Pool)
+    }
+
+    /// ユーザー登録を行う(`queries::register_user` を参照)
+    pub fn register_user(&self, name: &str, email: &Email, password: &str) -> Result<(models::User, models::Post), BrogError> {
+        let mut conn = self.conn()?;
+        queries::register_user(&mut conn, name, email, password)
+    }
+
+    /// IDでユーザーを取得する(`queries::get_user_by_id` を参照)
+    pub fn get_user_by_id(&self, user_id: i32) -> Result<models::User, BrogError> {
+        let mut conn = self.conn()?;
+        Ok(queries::get_user_by_id(&mut conn, user_id)?)
+    }
+
+    /// メールアドレスで有効なユーザーを取得する(`queries::get_active_user_by_email` を参照)
+    pub fn get_active_user_by_email(&self, email: &Email) -> Result<Option<models::User>, BrogError> {
+        let mut conn = self.conn()?;
+        Ok(queries::get_active_user_by_email(&mut conn, email)?)
+    }
+
+    /// 検証済みの `NewPost` から投稿を作成する(`queries::create_post` を参照)
+    pub fn create_post(&self, new_post: &models::NewPost) -> Result<models::Post, BrogError> {
+        let mut conn = self.conn()?;
+        queries::create_post(
+            &mut conn,
+            &new_post.title,
+            &new_post.post_body,
+            new_post.published,
+            new_post.user_id,
+        )
+    }
+
+    /// 投稿を更新する(`queries::update_post` を参照)
+    pub fn update_post(&self, post_id: i32, title: &str, body: &str, is_published: bool) -> Result<models::Post, BrogError> {
+        let mut conn = self.conn()?;
+        queries::update_post(&mut conn, post_id, title, body, is_published)
+    }
+
+    /// 権限を確認して投稿を削除する(`queries::delete_post_as` を参照)
+    pub fn delete_post_as(&self, post_id: i32, requester_user_id: i32) -> Result<queries::DeleteOutcome, BrogError> {
+        let mut conn = self.conn()?;
+        queries::delete_post_as(&mut conn, post_id, requester_user_id)
+    }
+
+    /// 投稿詳細を読み込む(`queries::load_post_detail` を参照)
+    pub fn load_post_detail(&self, post_id: i32, viewer: Option<i32>) -> Result<models::PostDetail, BrogError> {
+        let mut conn = self.conn()?;
+        Ok(queries::load_post_detail(&mut conn, post_id, viewer)?)
+    }
+
+    /// カーソルを使って公開済みの投稿を取得する(`queries::list_posts_after` を参照)
+    pub fn list_posts_after(&self, after_post_id: Option<i32>, limit: i64) -> Result<Vec<models::Post>, BrogError> {
+        let mut conn = self.conn()?;
+        Ok(queries::list_posts_after(&mut conn, after_post_id, limit)?)
+    }
+
+    /// 投稿を検索する(`queries::search_posts_ranked` を参照)
+    pub fn search_posts_ranked(&self, query: &str) -> Result<Vec<(models::Post, i32)>, BrogError> {
+        let mut conn = self.conn()?;
+        Ok(queries::search_posts_ranked(&mut conn, query)?)
+    }
+
+    /// 検証済みの `NewComment` からコメントを作成する(`queries::create_comment` を参照)
+    pub fn create_comment(&self, new_comment: &models::NewComment) -> Result<models::Comment, BrogError> {
+        let mut conn = self.conn()?;
+        queries::create_comment(&mut conn, new_comment.user_id, new_comment.post_id, &new_comment.comment_body)
+    }
+}
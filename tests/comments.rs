@@ -0,0 +1,674 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::queries::{self, DeleteOutcome};
+use brog_app::db::{schema, DbConnection};
+use brog_app::error::BrogError;
+use diesel::prelude::*;
+
+#[test]
+fn comment_author_can_delete_comment() {
+    let mut conn = common::connection();
+    let post_author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let post = common::post(&mut conn, post_author.user_id(), true);
+    let comment = common::comment(&mut conn, commenter.user_id(), post.post_id());
+
+    let outcome = queries::delete_comment_as(&mut conn, comment.comment_id(), commenter.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+}
+
+#[test]
+fn post_author_can_delete_comment() {
+    let mut conn = common::connection();
+    let post_author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let post = common::post(&mut conn, post_author.user_id(), true);
+    let comment = common::comment(&mut conn, commenter.user_id(), post.post_id());
+
+    let outcome = queries::delete_comment_as(&mut conn, comment.comment_id(), post_author.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+}
+
+#[test]
+fn admin_can_delete_comment() {
+    let mut conn = common::connection();
+    let post_author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let admin = common::admin(&mut conn);
+    let post = common::post(&mut conn, post_author.user_id(), true);
+    let comment = common::comment(&mut conn, commenter.user_id(), post.post_id());
+
+    let outcome = queries::delete_comment_as(&mut conn, comment.comment_id(), admin.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+}
+
+#[test]
+fn stranger_cannot_delete_comment() {
+    let mut conn = common::connection();
+    let post_author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let stranger = common::user(&mut conn);
+    let post = common::post(&mut conn, post_author.user_id(), true);
+    let comment = common::comment(&mut conn, commenter.user_id(), post.post_id());
+
+    let result = queries::delete_comment_as(&mut conn, comment.comment_id(), stranger.user_id());
+    assert!(matches!(result, Err(BrogError::Forbidden)));
+    assert_eq!(queries::get_comment_with_post(&mut conn, comment.comment_id()).unwrap().0.comment_id(), comment.comment_id());
+}
+
+#[test]
+fn delete_missing_comment_is_not_found() {
+    let mut conn = common::connection();
+    let admin = common::admin(&mut conn);
+
+    let outcome = queries::delete_comment_as(&mut conn, -1, admin.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::NotFound));
+}
+
+#[test]
+fn deleting_comment_keeps_its_replies_as_top_level_comments() {
+    let mut conn = common::connection();
+    let post_author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let post = common::post(&mut conn, post_author.user_id(), true);
+    let parent = common::comment(&mut conn, commenter.user_id(), post.post_id());
+    let reply = queries::create_reply(&mut conn, post_author.user_id(), parent.comment_id(), "reply").unwrap();
+
+    let outcome = queries::delete_comment_as(&mut conn, parent.comment_id(), commenter.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+
+    let (remaining, _) = queries::get_comment_with_post(&mut conn, reply.comment_id()).unwrap();
+    assert_eq!(remaining.parent_comment_id(), None);
+}
+
+#[test]
+fn find_and_delete_orphaned_comments() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let orphan = common::comment(&mut conn, author.user_id(), post.post_id());
+    let other_post = common::post(&mut conn, author.user_id(), true);
+    common::comment(&mut conn, author.user_id(), other_post.post_id());
+
+    // カスケードせずに投稿だけを削除して孤立したコメントを作る
+    common::without_foreign_key_checks(&mut conn, |conn| queries::delete_post(conn, post.post_id()).unwrap());
+
+    let orphans = queries::find_orphaned_comments(&mut conn).unwrap();
+    assert_eq!(orphans.iter().map(|c| c.comment_id()).collect::<Vec<_>>(), vec![orphan.comment_id()]);
+
+    assert_eq!(queries::delete_orphaned_comments(&mut conn).unwrap(), 1);
+    assert!(queries::find_orphaned_comments(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn delete_orphaned_comments_detaches_replies_of_orphans() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let departed = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let orphan = common::comment(&mut conn, departed.user_id(), post.post_id());
+    let reply = queries::create_reply(&mut conn, author.user_id(), orphan.comment_id(), "reply").unwrap();
+
+    // カスケードせずにユーザーだけを削除して、返信の付いた孤立したコメントを作る
+    common::without_foreign_key_checks(&mut conn, |conn| {
+        diesel::delete(schema::users::table.find(departed.user_id())).execute(conn).unwrap()
+    });
+
+    assert_eq!(queries::delete_orphaned_comments(&mut conn).unwrap(), 1);
+    let (remaining, _) = queries::get_comment_with_post(&mut conn, reply.comment_id()).unwrap();
+    assert_eq!(remaining.parent_comment_id(), None);
+    assert!(queries::find_orphaned_comments(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn get_recent_comments_lists_newest_across_posts_first() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let first_post = common::post(&mut conn, author.user_id(), true);
+    let second_post = common::post(&mut conn, author.user_id(), true);
+    let oldest = common::comment(&mut conn, author.user_id(), first_post.post_id());
+    let middle = common::comment(&mut conn, author.user_id(), second_post.post_id());
+    let newest = common::comment(&mut conn, author.user_id(), first_post.post_id());
+
+    let recent = queries::get_recent_comments(&mut conn, 3).unwrap();
+    let ids: Vec<i32> = recent.iter().map(|(comment, _, _)| comment.comment_id()).collect();
+    assert_eq!(ids, vec![newest.comment_id(), middle.comment_id(), oldest.comment_id()]);
+    let (_, commenter, post) = &recent[0];
+    assert_eq!(commenter.user_id(), author.user_id());
+    assert_eq!(post.post_id(), first_post.post_id());
+}
+
+#[test]
+fn get_recent_comments_excludes_comments_on_hidden_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let deactivated = common::user(&mut conn);
+    let visible = common::post(&mut conn, author.user_id(), true);
+    let draft = common::post(&mut conn, author.user_id(), false);
+    let scheduled = common::post(&mut conn, author.user_id(), false);
+    queries::schedule_post(&mut conn, scheduled.post_id(), Some(common::datetime(2999, 1, 1, 0))).unwrap();
+    let by_deactivated = common::post(&mut conn, deactivated.user_id(), true);
+    let shown = common::comment(&mut conn, author.user_id(), visible.post_id());
+    for post in [&draft, &scheduled, &by_deactivated] {
+        common::comment(&mut conn, author.user_id(), post.post_id());
+    }
+    queries::deactivate_user(&mut conn, deactivated.user_id()).unwrap();
+
+    let ids: Vec<i32> = queries::get_recent_comments(&mut conn, 10)
+        .unwrap()
+        .iter()
+        .map(|(comment, _, _)| comment.comment_id())
+        .collect();
+    assert_eq!(ids, vec![shown.comment_id()]);
+}
+
+#[test]
+fn get_recent_comments_orders_by_creation_time() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let backdated = common::comment(&mut conn, author.user_id(), post.post_id());
+    let earlier = common::comment(&mut conn, author.user_id(), post.post_id());
+    common::set_comment_created_at(&mut conn, backdated.comment_id(), common::datetime(2024, 1, 2, 0));
+    common::set_comment_created_at(&mut conn, earlier.comment_id(), common::datetime(2024, 1, 1, 0));
+
+    let ids: Vec<i32> = queries::get_recent_comments(&mut conn, 10)
+        .unwrap()
+        .iter()
+        .map(|(comment, _, _)| comment.comment_id())
+        .collect();
+    assert_eq!(ids, vec![backdated.comment_id(), earlier.comment_id()]);
+}
+
+#[test]
+fn update_comment_checked_accepts_current_timestamp() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = common::comment(&mut conn, author.user_id(), post.post_id());
+
+    let updated = queries::update_comment_checked(&mut conn, comment.comment_id(), "fixed typo", comment.updated_at()).unwrap();
+    assert_eq!(updated.comment_body(), "fixed typo");
+}
+
+#[test]
+fn update_comment_checked_rejects_stale_update() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = common::comment(&mut conn, author.user_id(), post.post_id());
+    // 編集を開始した後に他の編集者が更新した状態にする
+    let stale = comment.updated_at() - chrono::Duration::seconds(30);
+
+    let result = queries::update_comment_checked(&mut conn, comment.comment_id(), "overwrite", stale);
+    assert!(matches!(result, Err(BrogError::Conflict)));
+    let (current, _) = queries::get_comment_with_post(&mut conn, comment.comment_id()).unwrap();
+    assert_eq!(current.comment_body(), comment.comment_body());
+}
+
+#[test]
+fn update_comment_checked_on_missing_comment_is_not_found() {
+    let mut conn = common::connection();
+    let now = common::datetime(2020, 1, 1, 0);
+
+    let result = queries::update_comment_checked(&mut conn, -1, "body", now);
+    assert!(matches!(result, Err(BrogError::NotFound)));
+}
+
+#[test]
+fn create_comment_enforces_character_limit() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+
+    let at_limit = "a".repeat(queries::MAX_COMMENT_CHARS);
+    assert!(queries::create_comment(&mut conn, author.user_id(), post.post_id(), &at_limit).is_ok());
+
+    let over_limit = "a".repeat(queries::MAX_COMMENT_CHARS + 1);
+    let result = queries::create_comment(&mut conn, author.user_id(), post.post_id(), &over_limit);
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+}
+
+#[test]
+fn notifiable_emails_skip_opted_out_users() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let subscribed = common::user(&mut conn);
+    let opted_out = common::user(&mut conn);
+    let bystander = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    common::comment(&mut conn, subscribed.user_id(), post.post_id());
+    common::comment(&mut conn, subscribed.user_id(), post.post_id());
+    common::comment(&mut conn, opted_out.user_id(), post.post_id());
+    common::set_notifications_enabled(&mut conn, opted_out.user_id(), false);
+
+    let mut expected = vec![author.email().to_string(), subscribed.email().to_string()];
+    expected.sort();
+    let emails = queries::get_notifiable_emails_for_post_commenters(&mut conn, post.post_id()).unwrap();
+    assert_eq!(emails, expected);
+    assert!(!emails.contains(&bystander.email().to_string()));
+}
+
+#[test]
+fn get_comment_depth_counts_ancestors() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let top = common::comment(&mut conn, author.user_id(), post.post_id());
+    let reply = queries::create_reply(&mut conn, author.user_id(), top.comment_id(), "reply").unwrap();
+    let nested = queries::create_reply(&mut conn, author.user_id(), reply.comment_id(), "nested").unwrap();
+
+    assert_eq!(queries::get_comment_depth(&mut conn, top.comment_id()).unwrap(), 0);
+    assert_eq!(queries::get_comment_depth(&mut conn, nested.comment_id()).unwrap(), 2);
+    assert!(matches!(queries::get_comment_depth(&mut conn, -1), Err(BrogError::NotFound)));
+}
+
+#[test]
+fn get_thread_flat_orders_replies_under_parents_with_depth() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let first = common::comment(&mut conn, author.user_id(), post.post_id());
+    let second = common::comment(&mut conn, author.user_id(), post.post_id());
+    let reply = queries::create_reply(&mut conn, author.user_id(), first.comment_id(), "reply").unwrap();
+    let nested = queries::create_reply(&mut conn, author.user_id(), reply.comment_id(), "nested").unwrap();
+
+    let flat: Vec<(i32, u32)> = queries::get_thread_flat(&mut conn, post.post_id())
+        .unwrap()
+        .iter()
+        .map(|(comment, depth)| (comment.comment_id(), *depth))
+        .collect();
+    assert_eq!(
+        flat,
+        vec![
+            (first.comment_id(), 0),
+            (reply.comment_id(), 1),
+            (nested.comment_id(), 2),
+            (second.comment_id(), 0),
+        ]
+    );
+}
+
+#[test]
+fn get_thread_flat_keeps_comments_in_a_parent_cycle() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let a = common::comment(&mut conn, author.user_id(), post.post_id());
+    let b = queries::create_reply(&mut conn, author.user_id(), a.comment_id(), "b").unwrap();
+    let c = queries::create_reply(&mut conn, author.user_id(), b.comment_id(), "c").unwrap();
+    // a -> c -> b -> a と循環させる
+    common::set_comment_parent(&mut conn, a.comment_id(), Some(c.comment_id()));
+
+    let flat: Vec<(i32, u32)> = queries::get_thread_flat(&mut conn, post.post_id())
+        .unwrap()
+        .iter()
+        .map(|(comment, depth)| (comment.comment_id(), *depth))
+        .collect();
+    assert_eq!(flat, vec![(a.comment_id(), 0), (b.comment_id(), 1), (c.comment_id(), 2)]);
+}
+
+#[test]
+fn get_top_commenters_orders_by_comment_count_and_skips_silent_users() {
+    let mut conn = common::connection();
+    let top = common::user(&mut conn);
+    let second = common::user(&mut conn);
+    let silent = common::user(&mut conn);
+    let post = common::post(&mut conn, silent.user_id(), true);
+    for _ in 0..3 {
+        common::comment(&mut conn, top.user_id(), post.post_id());
+    }
+    common::comment(&mut conn, second.user_id(), post.post_id());
+
+    let ranking: Vec<(i32, i64)> = queries::get_top_commenters(&mut conn, 10)
+        .unwrap()
+        .iter()
+        .map(|(user, count)| (user.user_id(), *count))
+        .collect();
+    assert_eq!(ranking, vec![(top.user_id(), 3), (second.user_id(), 1)]);
+}
+
+#[test]
+fn get_comment_participants_lists_each_commenter_once() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let repeat = common::user(&mut conn);
+    let once = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    common::comment(&mut conn, repeat.user_id(), post.post_id());
+    common::comment(&mut conn, repeat.user_id(), post.post_id());
+    common::comment(&mut conn, once.user_id(), post.post_id());
+    common::comment(&mut conn, author.user_id(), post.post_id());
+
+    let ids = |users: Vec<brog_app::models::PublicUser>| users.iter().map(|user| user.user_id()).collect::<Vec<_>>();
+    assert_eq!(
+        ids(queries::get_comment_participants(&mut conn, post.post_id(), false).unwrap()),
+        vec![author.user_id(), repeat.user_id(), once.user_id()]
+    );
+    assert_eq!(
+        ids(queries::get_comment_participants(&mut conn, post.post_id(), true).unwrap()),
+        vec![repeat.user_id(), once.user_id()]
+    );
+}
+
+#[test]
+fn create_comment_is_rejected_on_closed_post() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    assert!(post.comments_enabled());
+    queries::set_comments_enabled(&mut conn, post.post_id(), false).unwrap();
+
+    let result = queries::create_comment(&mut conn, author.user_id(), post.post_id(), "too late");
+    assert!(matches!(result, Err(BrogError::CommentsClosed)));
+    assert!(queries::get_thread_flat(&mut conn, post.post_id()).unwrap().is_empty());
+}
+
+#[test]
+fn create_comment_is_accepted_on_reopened_post() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    queries::set_comments_enabled(&mut conn, post.post_id(), false).unwrap();
+    queries::set_comments_enabled(&mut conn, post.post_id(), true).unwrap();
+
+    let comment = queries::create_comment(&mut conn, author.user_id(), post.post_id(), "welcome back").unwrap();
+    assert_eq!(comment.post_id(), post.post_id());
+}
+
+#[test]
+fn count_comments_since_counts_only_comments_inside_window() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let post = common::post(&mut conn, other.user_id(), true);
+    let old = common::comment(&mut conn, user.user_id(), post.post_id());
+    let recent = common::comment(&mut conn, user.user_id(), post.post_id());
+    let latest = common::comment(&mut conn, user.user_id(), post.post_id());
+    let others = common::comment(&mut conn, other.user_id(), post.post_id());
+    common::set_comment_created_at(&mut conn, old.comment_id(), common::datetime(2024, 5, 1, 11));
+    common::set_comment_created_at(&mut conn, recent.comment_id(), common::datetime(2024, 5, 1, 12));
+    common::set_comment_created_at(&mut conn, latest.comment_id(), common::datetime(2024, 5, 1, 13));
+    common::set_comment_created_at(&mut conn, others.comment_id(), common::datetime(2024, 5, 1, 13));
+
+    let since = common::datetime(2024, 5, 1, 12);
+    assert_eq!(queries::count_comments_since(&mut conn, user.user_id(), since).unwrap(), 2);
+    assert_eq!(queries::count_comments_since(&mut conn, user.user_id(), common::datetime(2024, 5, 1, 14)).unwrap(), 0);
+}
+
+#[test]
+fn comment_is_marked_edited_only_after_body_changes() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = common::comment(&mut conn, author.user_id(), post.post_id());
+    assert!(!comment.edited());
+
+    let unchanged = queries::update_comment(&mut conn, comment.comment_id(), comment.comment_body()).unwrap();
+    assert!(!unchanged.edited());
+
+    let changed = queries::update_comment(&mut conn, comment.comment_id(), "fixed typo").unwrap();
+    assert!(changed.edited());
+    assert_eq!(changed.comment_body(), "fixed typo");
+}
+
+/// コメントツリーをコメントIDの入れ子で表す
+fn tree_ids(nodes: &[brog_app::models::CommentNode]) -> Vec<(i32, Vec<i32>)> {
+    nodes
+        .iter()
+        .map(|node| {
+            let mut descendants = Vec::new();
+            let mut stack: Vec<&brog_app::models::CommentNode> = node.children.iter().rev().collect();
+            while let Some(child) = stack.pop() {
+                descendants.push(child.comment.comment_id());
+                stack.extend(child.children.iter().rev());
+            }
+            (node.comment.comment_id(), descendants)
+        })
+        .collect()
+}
+
+#[test]
+fn get_comment_tree_nests_replies_under_parents() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let first = common::comment(&mut conn, author.user_id(), post.post_id());
+    let second = common::comment(&mut conn, author.user_id(), post.post_id());
+    let reply = queries::create_reply(&mut conn, author.user_id(), first.comment_id(), "reply").unwrap();
+    let nested = queries::create_reply(&mut conn, author.user_id(), reply.comment_id(), "nested").unwrap();
+    let sibling = queries::create_reply(&mut conn, author.user_id(), first.comment_id(), "sibling").unwrap();
+
+    let tree = queries::get_comment_tree(&mut conn, post.post_id()).unwrap();
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree[0].comment.comment_id(), first.comment_id());
+    let replies: Vec<i32> = tree[0].children.iter().map(|node| node.comment.comment_id()).collect();
+    assert_eq!(replies, vec![reply.comment_id(), sibling.comment_id()]);
+    assert_eq!(tree[0].children[0].children.len(), 1);
+    assert_eq!(tree[0].children[0].children[0].comment.comment_id(), nested.comment_id());
+    assert!(tree[0].children[1].children.is_empty());
+    assert_eq!(tree[1].comment.comment_id(), second.comment_id());
+    assert!(tree[1].children.is_empty());
+}
+
+#[test]
+fn get_comment_tree_keeps_comments_in_a_parent_cycle() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let root = common::comment(&mut conn, author.user_id(), post.post_id());
+    let a = common::comment(&mut conn, author.user_id(), post.post_id());
+    let b = queries::create_reply(&mut conn, author.user_id(), a.comment_id(), "b").unwrap();
+    let under_b = queries::create_reply(&mut conn, author.user_id(), b.comment_id(), "under b").unwrap();
+    // a -> b -> a と循環させる
+    common::set_comment_parent(&mut conn, a.comment_id(), Some(b.comment_id()));
+
+    let tree = queries::get_comment_tree(&mut conn, post.post_id()).unwrap();
+    assert_eq!(
+        tree_ids(&tree),
+        vec![
+            (root.comment_id(), vec![]),
+            (a.comment_id(), vec![b.comment_id(), under_b.comment_id()]),
+        ]
+    );
+}
+
+#[test]
+fn get_sibling_comments_excludes_source_comment() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let other_post = common::post(&mut conn, author.user_id(), true);
+    let first = common::comment(&mut conn, author.user_id(), post.post_id());
+    let source = common::comment(&mut conn, author.user_id(), post.post_id());
+    let third = common::comment(&mut conn, author.user_id(), post.post_id());
+    common::comment(&mut conn, author.user_id(), other_post.post_id());
+    common::set_comment_created_at(&mut conn, first.comment_id(), common::datetime(2024, 1, 1, 0));
+    common::set_comment_created_at(&mut conn, source.comment_id(), common::datetime(2024, 1, 2, 0));
+    common::set_comment_created_at(&mut conn, third.comment_id(), common::datetime(2024, 1, 3, 0));
+
+    let ids = |comments: Vec<brog_app::models::Comment>| comments.iter().map(|comment| comment.comment_id()).collect::<Vec<_>>();
+    assert_eq!(
+        ids(queries::get_sibling_comments(&mut conn, source.comment_id(), 10).unwrap()),
+        vec![third.comment_id(), first.comment_id()]
+    );
+    assert_eq!(ids(queries::get_sibling_comments(&mut conn, source.comment_id(), 1).unwrap()), vec![third.comment_id()]);
+}
+
+/// コメントでメンションされたユーザーのID
+fn mentioned_user_ids(conn: &mut DbConnection, comment_id: i32) -> Vec<i32> {
+    schema::comment_mentions::table
+        .filter(schema::comment_mentions::comment_id.eq(comment_id))
+        .select(schema::comment_mentions::mentioned_user_id)
+        .order(schema::comment_mentions::mentioned_user_id.asc())
+        .load(conn)
+        .unwrap()
+}
+
+#[test]
+fn create_comment_records_mentions_of_existing_users() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let mentioned = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+
+    let body = format!("hi @{} and again @{}", mentioned.name(), mentioned.name());
+    let comment = queries::create_comment(&mut conn, author.user_id(), post.post_id(), &body).unwrap();
+    assert_eq!(mentioned_user_ids(&mut conn, comment.comment_id()), vec![mentioned.user_id()]);
+}
+
+#[test]
+fn create_comment_ignores_mentions_of_unknown_users() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+
+    let body = format!("hello @{}", common::unique_name("nobody"));
+    let comment = queries::create_comment(&mut conn, author.user_id(), post.post_id(), &body).unwrap();
+    assert!(mentioned_user_ids(&mut conn, comment.comment_id()).is_empty());
+}
+
+#[test]
+fn edit_count_increments_on_each_body_change() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = common::comment(&mut conn, author.user_id(), post.post_id());
+    assert_eq!(comment.edit_count(), 0);
+
+    queries::update_comment(&mut conn, comment.comment_id(), "first edit").unwrap();
+    queries::update_comment(&mut conn, comment.comment_id(), "first edit").unwrap();
+    let edited = queries::update_comment(&mut conn, comment.comment_id(), "second edit").unwrap();
+    assert_eq!(edited.edit_count(), 2);
+}
+
+#[test]
+fn get_comment_with_post_returns_the_commented_post() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    common::post(&mut conn, author.user_id(), true);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = common::comment(&mut conn, author.user_id(), post.post_id());
+
+    let (found, commented_post) = queries::get_comment_with_post(&mut conn, comment.comment_id()).unwrap();
+    assert_eq!(found.comment_id(), comment.comment_id());
+    assert_eq!(commented_post.post_id(), post.post_id());
+    assert_eq!(commented_post.title(), post.title());
+    assert!(matches!(
+        queries::get_comment_with_post(&mut conn, comment.comment_id() + 1000),
+        Err(diesel::result::Error::NotFound)
+    ));
+}
+
+#[test]
+fn count_replies_counts_only_direct_replies() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let parent = common::comment(&mut conn, author.user_id(), post.post_id());
+    let first = queries::create_reply(&mut conn, author.user_id(), parent.comment_id(), "first").unwrap();
+    queries::create_reply(&mut conn, author.user_id(), parent.comment_id(), "second").unwrap();
+    queries::create_reply(&mut conn, author.user_id(), first.comment_id(), "nested").unwrap();
+
+    assert_eq!(queries::count_replies(&mut conn, parent.comment_id()).unwrap(), 2);
+    assert_eq!(queries::count_replies(&mut conn, first.comment_id()).unwrap(), 1);
+}
+
+#[test]
+fn count_comments_on_user_posts_since_counts_new_comments_from_others() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let reader = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let other_post = common::post(&mut conn, reader.user_id(), true);
+    let before = common::comment(&mut conn, reader.user_id(), post.post_id());
+    let after = common::comment(&mut conn, reader.user_id(), post.post_id());
+    let own = common::comment(&mut conn, author.user_id(), post.post_id());
+    let elsewhere = common::comment(&mut conn, author.user_id(), other_post.post_id());
+    common::set_comment_created_at(&mut conn, before.comment_id(), common::datetime(2024, 6, 1, 9));
+    common::set_comment_created_at(&mut conn, after.comment_id(), common::datetime(2024, 6, 1, 11));
+    common::set_comment_created_at(&mut conn, own.comment_id(), common::datetime(2024, 6, 1, 11));
+    common::set_comment_created_at(&mut conn, elsewhere.comment_id(), common::datetime(2024, 6, 1, 11));
+
+    let since = common::datetime(2024, 6, 1, 10);
+    assert_eq!(queries::count_comments_on_user_posts_since(&mut conn, author.user_id(), since).unwrap(), 1);
+}
+
+#[test]
+fn notification_context_bundles_comment_commenter_and_post_title() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = queries::create_comment(&mut conn, commenter.user_id(), post.post_id(), "nice post").unwrap();
+
+    let context = queries::notification_context(&mut conn, comment.comment_id()).unwrap();
+    assert_eq!(context.comment.comment_id(), comment.comment_id());
+    assert_eq!(context.comment.comment_body(), "nice post");
+    assert_eq!(context.commenter_name, commenter.name());
+    assert_eq!(context.post_title, post.title());
+}
+
+#[test]
+fn delete_comment_returning_returns_deleted_comment() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = queries::create_comment(&mut conn, author.user_id(), post.post_id(), "to be removed").unwrap();
+    let reply = queries::create_reply(&mut conn, author.user_id(), comment.comment_id(), "reply").unwrap();
+
+    let deleted = queries::delete_comment_returning(&mut conn, comment.comment_id()).unwrap().unwrap();
+    assert_eq!(deleted.comment_id(), comment.comment_id());
+    assert_eq!(deleted.comment_body(), "to be removed");
+    let remaining: Vec<i32> = queries::get_thread_flat(&mut conn, post.post_id())
+        .unwrap()
+        .iter()
+        .map(|(comment, _)| comment.comment_id())
+        .collect();
+    assert_eq!(remaining, vec![reply.comment_id()]);
+}
+
+#[test]
+fn delete_comment_returning_missing_comment_is_none() {
+    let mut conn = common::connection();
+    assert!(queries::delete_comment_returning(&mut conn, -1).unwrap().is_none());
+}
+
+#[test]
+fn get_comment_position_counts_earlier_comments_on_post() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let other_post = common::post(&mut conn, author.user_id(), true);
+    let comments: Vec<_> = (0..3).map(|_| common::comment(&mut conn, author.user_id(), post.post_id())).collect();
+    let elsewhere = common::comment(&mut conn, author.user_id(), other_post.post_id());
+
+    assert_eq!(queries::get_comment_position(&mut conn, post.post_id(), comments[0].comment_id()).unwrap(), 0);
+    assert_eq!(queries::get_comment_position(&mut conn, post.post_id(), comments[2].comment_id()).unwrap(), 2);
+    assert!(matches!(
+        queries::get_comment_position(&mut conn, post.post_id(), elsewhere.comment_id()),
+        Err(BrogError::NotFound)
+    ));
+}
+
+#[test]
+fn get_comments_for_posts_groups_comments_by_post() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let busy = common::post(&mut conn, author.user_id(), true);
+    let single = common::post(&mut conn, author.user_id(), true);
+    let empty = common::post(&mut conn, author.user_id(), true);
+    let not_requested = common::post(&mut conn, author.user_id(), true);
+    let busy_comments: Vec<i32> = (0..3).map(|_| common::comment(&mut conn, author.user_id(), busy.post_id()).comment_id()).collect();
+    let single_comment = common::comment(&mut conn, author.user_id(), single.post_id());
+    common::comment(&mut conn, author.user_id(), not_requested.post_id());
+
+    let grouped = queries::get_comments_for_posts(&mut conn, &[busy.post_id(), single.post_id(), empty.post_id()]).unwrap();
+    let ids = |post_id: i32| -> Vec<i32> { grouped[&post_id].iter().map(|comment| comment.comment_id()).collect() };
+    assert_eq!(grouped.len(), 3);
+    assert_eq!(ids(busy.post_id()), busy_comments);
+    assert_eq!(ids(single.post_id()), vec![single_comment.comment_id()]);
+    assert!(ids(empty.post_id()).is_empty());
+}
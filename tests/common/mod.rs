@@ -0,0 +1,170 @@
+//! 結合テスト共通モジュール
+//!
+//! `live-db` フィーチャー有効時の結合テストで使用する、データベース接続とテストデータ作成の処理を提供します。
+//! 接続先は環境変数 `DATABASE_URL` で指定します。
+//! 各テストの変更はテスト用トランザクション内で行われ、テスト終了時にロールバックされます。
+
+#![allow(dead_code)]
+
+use brog_app::db::migrations::run_pending_migrations;
+use brog_app::db::{queries, schema, DbConnection};
+use brog_app::email::Email;
+use brog_app::models;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Once;
+
+static MIGRATE: Once = Once::new();
+static NAME_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 環境変数 `DATABASE_URL` から接続先を取得する関数
+pub fn database_url() -> String {
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set to run live-db tests")
+}
+
+/// 同じサーバー上の別のデータベースを指す接続先を作成する関数
+pub fn database_url_for(database_name: &str) -> String {
+    let url = database_url();
+    let (server, _) = url.rsplit_once('/').expect("DATABASE_URL must contain a database name");
+    format!("{}/{}", server, database_name)
+}
+
+/// 指定された接続先に接続する関数
+pub fn connect(url: &str) -> DbConnection {
+    DbConnection::establish(url).unwrap_or_else(|e| panic!("failed to connect to {}: {}", url, e))
+}
+
+/// テスト用の接続を取得する関数
+///
+/// 初回のみ未適用のマイグレーションを適用し、その後テスト用トランザクションを開始した接続を返します。
+pub fn connection() -> DbConnection {
+    let mut conn = connect(&database_url());
+    MIGRATE.call_once(|| run_pending_migrations(&mut conn).expect("failed to run migrations"));
+    conn.begin_test_transaction().expect("failed to begin test transaction");
+    conn
+}
+
+/// 並行して実行される他のテストと重複しない名前を作成する関数
+pub fn unique_name(prefix: &str) -> String {
+    format!("{}_{}_{}", prefix, std::process::id(), NAME_COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// テスト用のユーザーを作成する関数
+pub fn user(conn: &mut DbConnection) -> models::User {
+    let name = unique_name("user");
+    let email = Email::try_from(format!("{}@example.com", name).as_str()).unwrap();
+    queries::create_user(conn, &name, &email, "hash").unwrap()
+}
+
+/// テスト用の管理者ユーザーを作成する関数
+pub fn admin(conn: &mut DbConnection) -> models::User {
+    let user = user(conn);
+    diesel::update(schema::users::table.find(user.user_id()))
+        .set(schema::users::is_admin.eq(true))
+        .execute(conn)
+        .unwrap();
+    queries::get_user_by_id(conn, user.user_id()).unwrap()
+}
+
+/// テスト用の投稿を作成する関数
+pub fn post(conn: &mut DbConnection, user_id: i32, published: bool) -> models::Post {
+    let title = unique_name("title");
+    queries::create_post(conn, &title, "body", published, user_id).unwrap()
+}
+
+/// テスト用のコメントを作成する関数
+pub fn comment(conn: &mut DbConnection, user_id: i32, post_id: i32) -> models::Comment {
+    queries::create_comment(conn, user_id, post_id, "comment").unwrap()
+}
+
+/// 投稿の作成日時を書き換える関数
+pub fn set_post_created_at(conn: &mut DbConnection, post_id: i32, created_at: NaiveDateTime) {
+    diesel::update(schema::posts::table.find(post_id))
+        .set(schema::posts::created_at.eq(created_at))
+        .execute(conn)
+        .unwrap();
+}
+
+/// コメントの作成日時を書き換える関数
+pub fn set_comment_created_at(conn: &mut DbConnection, comment_id: i32, created_at: NaiveDateTime) {
+    diesel::update(schema::comments::table.find(comment_id))
+        .set(schema::comments::created_at.eq(created_at))
+        .execute(conn)
+        .unwrap();
+}
+
+/// ユーザーの登録日時を書き換える関数
+pub fn set_user_created_at(conn: &mut DbConnection, user_id: i32, created_at: NaiveDateTime) {
+    diesel::update(schema::users::table.find(user_id))
+        .set(schema::users::created_at.eq(created_at))
+        .execute(conn)
+        .unwrap();
+}
+
+/// 日時を作成する関数
+pub fn datetime(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, 0, 0)
+        .unwrap()
+}
+
+/// 外部キー制約を無効にして処理を実行する関数
+///
+/// 整合性の崩れたデータ(孤立したコメントなど)を意図的に作成するテストで使用します。
+pub fn without_foreign_key_checks<T>(conn: &mut DbConnection, f: impl FnOnce(&mut DbConnection) -> T) -> T {
+    #[cfg(not(feature = "postgres"))]
+    const DISABLE: &str = "SET FOREIGN_KEY_CHECKS = 0";
+    #[cfg(not(feature = "postgres"))]
+    const ENABLE: &str = "SET FOREIGN_KEY_CHECKS = 1";
+    // PostgreSQLではレプリカとして振る舞うことで外部キーのトリガーを止める(スーパーユーザー権限が必要)
+    #[cfg(feature = "postgres")]
+    const DISABLE: &str = "SET session_replication_role = replica";
+    #[cfg(feature = "postgres")]
+    const ENABLE: &str = "SET session_replication_role = DEFAULT";
+
+    diesel::sql_query(DISABLE).execute(conn).unwrap();
+    let result = f(conn);
+    diesel::sql_query(ENABLE).execute(conn).unwrap();
+    result
+}
+
+/// ユーザーの通知設定を変更する関数
+pub fn set_notifications_enabled(conn: &mut DbConnection, user_id: i32, enabled: bool) {
+    diesel::update(schema::users::table.find(user_id))
+        .set(schema::users::notifications_enabled.eq(enabled))
+        .execute(conn)
+        .unwrap();
+}
+
+/// コメントの返信先を書き換える関数
+///
+/// 通常の操作では作成できない、返信先が循環したコメントを作成するテストで使用します。
+pub fn set_comment_parent(conn: &mut DbConnection, comment_id: i32, parent_comment_id: Option<i32>) {
+    diesel::update(schema::comments::table.find(comment_id))
+        .set(schema::comments::parent_comment_id.eq(parent_comment_id))
+        .execute(conn)
+        .unwrap();
+}
+
+/// 投稿を読み込み直す関数
+pub fn reload_post(conn: &mut DbConnection, post_id: i32) -> models::Post {
+    schema::posts::table.find(post_id).first(conn).unwrap()
+}
+
+/// 検証を通さずに投稿の本文を書き換える関数
+pub fn set_post_body(conn: &mut DbConnection, post_id: i32, body: &str) {
+    diesel::update(schema::posts::table.find(post_id))
+        .set(schema::posts::post_body.eq(body))
+        .execute(conn)
+        .unwrap();
+}
+
+/// 投稿をモデレーション待ちにする関数
+pub fn flag_for_review(conn: &mut DbConnection, post_id: i32) {
+    diesel::update(schema::posts::table.find(post_id))
+        .set(schema::posts::needs_review.eq(true))
+        .execute(conn)
+        .unwrap();
+}
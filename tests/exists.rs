@@ -0,0 +1,36 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::queries::ExistsById;
+use brog_app::models::{Comment, Post, User};
+
+#[test]
+fn user_exists_by_id() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+
+    assert!(User::exists(&mut conn, user.user_id()).unwrap());
+    assert!(!User::exists(&mut conn, -1).unwrap());
+}
+
+#[test]
+fn post_exists_by_id() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let post = common::post(&mut conn, user.user_id(), false);
+
+    assert!(Post::exists(&mut conn, post.post_id()).unwrap());
+    assert!(!Post::exists(&mut conn, -1).unwrap());
+}
+
+#[test]
+fn comment_exists_by_id() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let post = common::post(&mut conn, user.user_id(), true);
+    let comment = common::comment(&mut conn, user.user_id(), post.post_id());
+
+    assert!(Comment::exists(&mut conn, comment.comment_id()).unwrap());
+    assert!(!Comment::exists(&mut conn, -1).unwrap());
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::fixtures;
+use brog_app::db::{queries, schema, DbConnection};
+use brog_app::email::Email;
+use diesel::prelude::*;
+
+/// ユーザー、投稿、コメントの件数
+fn row_counts(conn: &mut DbConnection) -> (i64, i64, i64) {
+    (
+        schema::users::table.count().get_result(conn).unwrap(),
+        schema::posts::table.count().get_result(conn).unwrap(),
+        schema::comments::table.count().get_result(conn).unwrap(),
+    )
+}
+
+#[test]
+fn seed_demo_data_fills_empty_database_once() {
+    let mut conn = common::connection();
+    assert_eq!(row_counts(&mut conn), (0, 0, 0));
+
+    fixtures::seed_demo_data(&mut conn).unwrap();
+    let seeded = row_counts(&mut conn);
+    assert_eq!(seeded, (3, 3, 3));
+    let sentinel: i64 = schema::users::table
+        .filter(schema::users::email.eq(fixtures::DEMO_SENTINEL_EMAIL))
+        .count()
+        .get_result(&mut conn)
+        .unwrap();
+    assert_eq!(sentinel, 1);
+
+    fixtures::seed_demo_data(&mut conn).unwrap();
+    assert_eq!(row_counts(&mut conn), seeded);
+}
+
+#[test]
+fn truncate_all_empties_every_table() {
+    let mut conn = common::connection();
+    fixtures::seed_demo_data(&mut conn).unwrap();
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+    let post = common::post(&mut conn, alice.user_id(), true);
+    let comment = queries::create_comment(&mut conn, bob.user_id(), post.post_id(), &format!("hi @{}", alice.name())).unwrap();
+    queries::create_reply(&mut conn, alice.user_id(), comment.comment_id(), "reply").unwrap();
+    queries::follow_user(&mut conn, bob.user_id(), alice.user_id()).unwrap();
+    queries::toggle_like(&mut conn, bob.user_id(), post.post_id()).unwrap();
+    queries::add_tag_to_post(&mut conn, post.post_id(), &common::unique_name("tag")).unwrap();
+    queries::request_password_reset(&mut conn, &Email::try_from(alice.email()).unwrap()).unwrap();
+
+    fixtures::truncate_all(&mut conn).unwrap();
+
+    assert_eq!(row_counts(&mut conn), (0, 0, 0));
+    let remaining: [i64; 7] = [
+        schema::comment_mentions::table.count().get_result(&mut conn).unwrap(),
+        schema::follows::table.count().get_result(&mut conn).unwrap(),
+        schema::password_resets::table.count().get_result(&mut conn).unwrap(),
+        schema::post_likes::table.count().get_result(&mut conn).unwrap(),
+        schema::post_revisions::table.count().get_result(&mut conn).unwrap(),
+        schema::post_tags::table.count().get_result(&mut conn).unwrap(),
+        schema::tags::table.count().get_result(&mut conn).unwrap(),
+    ];
+    assert_eq!(remaining, [0; 7]);
+}
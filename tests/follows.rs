@@ -0,0 +1,49 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::queries;
+use brog_app::error::BrogError;
+
+#[test]
+fn follow_and_unfollow_user() {
+    let mut conn = common::connection();
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+
+    let follow = queries::follow_user(&mut conn, alice.user_id(), bob.user_id()).unwrap();
+    assert_eq!(follow.follower_id(), alice.user_id());
+    assert_eq!(follow.followee_id(), bob.user_id());
+
+    let followers = queries::get_followers(&mut conn, bob.user_id()).unwrap();
+    assert_eq!(followers.iter().map(|u| u.user_id()).collect::<Vec<_>>(), vec![alice.user_id()]);
+    let following = queries::get_following(&mut conn, alice.user_id()).unwrap();
+    assert_eq!(following.iter().map(|u| u.user_id()).collect::<Vec<_>>(), vec![bob.user_id()]);
+    assert_eq!(queries::count_followers(&mut conn, bob.user_id()).unwrap(), 1);
+
+    assert_eq!(queries::unfollow_user(&mut conn, alice.user_id(), bob.user_id()).unwrap(), 1);
+    assert_eq!(queries::count_followers(&mut conn, bob.user_id()).unwrap(), 0);
+    // フォローしていない場合は何も削除しない
+    assert_eq!(queries::unfollow_user(&mut conn, alice.user_id(), bob.user_id()).unwrap(), 0);
+}
+
+#[test]
+fn follow_twice_is_conflict() {
+    let mut conn = common::connection();
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+
+    queries::follow_user(&mut conn, alice.user_id(), bob.user_id()).unwrap();
+    let result = queries::follow_user(&mut conn, alice.user_id(), bob.user_id());
+    assert!(matches!(result, Err(BrogError::Conflict)));
+}
+
+#[test]
+fn follow_self_is_rejected() {
+    let mut conn = common::connection();
+    let alice = common::user(&mut conn);
+
+    let result = queries::follow_user(&mut conn, alice.user_id(), alice.user_id());
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    assert_eq!(queries::count_followers(&mut conn, alice.user_id()).unwrap(), 0);
+}
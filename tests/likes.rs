@@ -0,0 +1,117 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::queries;
+
+#[test]
+fn toggle_like_twice_returns_to_not_liked() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let fan = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+
+    assert!(queries::toggle_like(&mut conn, fan.user_id(), post.post_id()).unwrap());
+    assert_eq!(queries::get_post_stats(&mut conn, post.post_id()).unwrap().likes, 1);
+
+    assert!(!queries::toggle_like(&mut conn, fan.user_id(), post.post_id()).unwrap());
+    assert_eq!(queries::get_post_stats(&mut conn, post.post_id()).unwrap().likes, 0);
+}
+
+#[test]
+fn get_liked_posts_returns_only_liked_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let fan = common::user(&mut conn);
+    let first = common::post(&mut conn, author.user_id(), true);
+    let second = common::post(&mut conn, author.user_id(), true);
+    common::post(&mut conn, author.user_id(), true);
+    queries::toggle_like(&mut conn, fan.user_id(), first.post_id()).unwrap();
+    queries::toggle_like(&mut conn, fan.user_id(), second.post_id()).unwrap();
+
+    let mut liked: Vec<i32> = queries::get_liked_posts(&mut conn, fan.user_id(), 10, 0)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    liked.sort();
+    assert_eq!(liked, vec![first.post_id(), second.post_id()]);
+}
+
+#[test]
+fn get_liked_posts_skips_posts_that_are_no_longer_visible() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let deactivated = common::user(&mut conn);
+    let fan = common::user(&mut conn);
+    let visible = common::post(&mut conn, author.user_id(), true);
+    let unpublished = common::post(&mut conn, author.user_id(), true);
+    let scheduled = common::post(&mut conn, author.user_id(), true);
+    let by_deactivated = common::post(&mut conn, deactivated.user_id(), true);
+    for post in [&visible, &unpublished, &scheduled, &by_deactivated] {
+        queries::toggle_like(&mut conn, fan.user_id(), post.post_id()).unwrap();
+    }
+    queries::set_published_for_ids(&mut conn, &[unpublished.post_id()], false).unwrap();
+    queries::schedule_post(&mut conn, scheduled.post_id(), Some(common::datetime(2999, 1, 1, 0))).unwrap();
+    queries::deactivate_user(&mut conn, deactivated.user_id()).unwrap();
+
+    let liked: Vec<i32> = queries::get_liked_posts(&mut conn, fan.user_id(), 10, 0)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    assert_eq!(liked, vec![visible.post_id()]);
+}
+
+#[test]
+fn get_posts_liked_by_following_ranks_by_followee_likes() {
+    let mut conn = common::connection();
+    let viewer = common::user(&mut conn);
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+    let stranger = common::user(&mut conn);
+    let author = common::user(&mut conn);
+    let popular = common::post(&mut conn, author.user_id(), true);
+    let liked_once = common::post(&mut conn, author.user_id(), true);
+    let stranger_pick = common::post(&mut conn, author.user_id(), true);
+    let draft = common::post(&mut conn, author.user_id(), false);
+    queries::follow_user(&mut conn, viewer.user_id(), alice.user_id()).unwrap();
+    queries::follow_user(&mut conn, viewer.user_id(), bob.user_id()).unwrap();
+    for (user, post) in [
+        (&alice, &popular),
+        (&bob, &popular),
+        (&alice, &liked_once),
+        (&alice, &draft),
+        (&stranger, &stranger_pick),
+        (&stranger, &popular),
+    ] {
+        queries::toggle_like(&mut conn, user.user_id(), post.post_id()).unwrap();
+    }
+
+    let recommended: Vec<(i32, i64)> = queries::get_posts_liked_by_following(&mut conn, viewer.user_id(), 10)
+        .unwrap()
+        .iter()
+        .map(|(post, likes)| (post.post_id(), *likes))
+        .collect();
+    assert_eq!(recommended, vec![(popular.post_id(), 2), (liked_once.post_id(), 1)]);
+}
+
+#[test]
+fn list_posts_with_like_status_marks_posts_the_viewer_liked() {
+    let mut conn = common::connection();
+    let viewer = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let author = common::user(&mut conn);
+    let liked = common::post(&mut conn, author.user_id(), true);
+    let not_liked = common::post(&mut conn, author.user_id(), true);
+    queries::toggle_like(&mut conn, viewer.user_id(), liked.post_id()).unwrap();
+    queries::toggle_like(&mut conn, other.user_id(), not_liked.post_id()).unwrap();
+
+    let mut status: Vec<(i32, bool)> = queries::list_posts_with_like_status(&mut conn, viewer.user_id(), 10, 0)
+        .unwrap()
+        .iter()
+        .map(|(post, liked)| (post.post_id(), *liked))
+        .collect();
+    status.sort();
+    assert_eq!(status, vec![(liked.post_id(), true), (not_liked.post_id(), false)]);
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::migrations::{run_pending_migrations, MIGRATIONS};
+use brog_app::db::schema;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+/// マイグレーションの確認に使用するデータベース名
+const EMPTY_DATABASE: &str = "brog_app_migrations_test";
+
+#[test]
+fn run_pending_migrations_creates_tables_on_empty_database() {
+    let mut server = common::connect(&common::database_url());
+    diesel::sql_query(format!("DROP DATABASE IF EXISTS {}", EMPTY_DATABASE)).execute(&mut server).unwrap();
+    diesel::sql_query(format!("CREATE DATABASE {}", EMPTY_DATABASE)).execute(&mut server).unwrap();
+
+    {
+        let mut conn = common::connect(&common::database_url_for(EMPTY_DATABASE));
+        assert!(schema::users::table.count().get_result::<i64>(&mut conn).is_err());
+
+        run_pending_migrations(&mut conn).unwrap();
+        assert!(!conn.has_pending_migration(MIGRATIONS).unwrap());
+
+        assert_eq!(schema::users::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::posts::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::comments::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::follows::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::password_resets::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::post_likes::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::tags::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::post_tags::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::comment_mentions::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+        assert_eq!(schema::post_revisions::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+
+        // 適用済みの場合は何もしない
+        run_pending_migrations(&mut conn).unwrap();
+    }
+
+    diesel::sql_query(format!("DROP DATABASE {}", EMPTY_DATABASE)).execute(&mut server).unwrap();
+}
@@ -0,0 +1,866 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::queries::{self, DeleteOutcome};
+use brog_app::db::{schema, DbConnection};
+use brog_app::error::BrogError;
+use brog_app::models::DiffLine;
+use diesel::prelude::*;
+
+#[test]
+fn build_post_view_nests_author_and_comments() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let first = queries::create_comment(&mut conn, commenter.user_id(), post.post_id(), "first").unwrap();
+    let second = queries::create_comment(&mut conn, author.user_id(), post.post_id(), "second").unwrap();
+
+    let view = queries::build_post_view(&mut conn, post.post_id()).unwrap();
+    assert_eq!(view.post.post_id(), post.post_id());
+    assert_eq!(view.author.user_id(), author.user_id());
+    assert_eq!(view.comments.len(), 2);
+    assert_eq!(view.comments[0].comment.comment_id(), first.comment_id());
+    assert_eq!(view.comments[0].author.user_id(), commenter.user_id());
+    assert_eq!(view.comments[1].comment.comment_id(), second.comment_id());
+    assert_eq!(view.comments[1].author.user_id(), author.user_id());
+}
+
+#[test]
+fn build_post_view_for_missing_post_is_not_found() {
+    let mut conn = common::connection();
+    let result = queries::build_post_view(&mut conn, -1);
+    assert!(matches!(result, Err(diesel::result::Error::NotFound)));
+}
+
+#[test]
+fn owner_can_delete_own_post() {
+    let mut conn = common::connection();
+    let owner = common::user(&mut conn);
+    let post = common::post(&mut conn, owner.user_id(), true);
+
+    let outcome = queries::delete_post_as(&mut conn, post.post_id(), owner.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+    assert!(queries::resolve_post(&mut conn, &post.post_id().to_string()).unwrap().is_none());
+}
+
+#[test]
+fn non_owner_cannot_delete_post() {
+    let mut conn = common::connection();
+    let owner = common::user(&mut conn);
+    let stranger = common::user(&mut conn);
+    let post = common::post(&mut conn, owner.user_id(), true);
+
+    let result = queries::delete_post_as(&mut conn, post.post_id(), stranger.user_id());
+    assert!(matches!(result, Err(BrogError::Forbidden)));
+    assert!(queries::resolve_post(&mut conn, &post.post_id().to_string()).unwrap().is_some());
+}
+
+#[test]
+fn admin_can_delete_any_post() {
+    let mut conn = common::connection();
+    let owner = common::user(&mut conn);
+    let admin = common::admin(&mut conn);
+    let post = common::post(&mut conn, owner.user_id(), true);
+
+    let outcome = queries::delete_post_as(&mut conn, post.post_id(), admin.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+}
+
+#[test]
+fn delete_missing_post_is_not_found() {
+    let mut conn = common::connection();
+    let admin = common::admin(&mut conn);
+
+    let outcome = queries::delete_post_as(&mut conn, -1, admin.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::NotFound));
+}
+
+#[test]
+fn delete_post_removes_comments_likes_and_tags() {
+    let mut conn = common::connection();
+    let owner = common::user(&mut conn);
+    let fan = common::user(&mut conn);
+    let post = common::post(&mut conn, owner.user_id(), true);
+    let comment = common::comment(&mut conn, fan.user_id(), post.post_id());
+    queries::create_reply(&mut conn, owner.user_id(), comment.comment_id(), &format!("thanks @{}", fan.name())).unwrap();
+    queries::toggle_like(&mut conn, fan.user_id(), post.post_id()).unwrap();
+    let tag = queries::add_tag_to_post(&mut conn, post.post_id(), &common::unique_name("tag")).unwrap();
+
+    let outcome = queries::delete_post_as(&mut conn, post.post_id(), owner.user_id()).unwrap();
+    assert!(matches!(outcome, DeleteOutcome::Deleted));
+    assert!(queries::get_post_ids_by_tag(&mut conn, tag.tag_id()).unwrap().is_empty());
+    assert!(queries::get_liked_posts(&mut conn, fan.user_id(), 10, 0).unwrap().is_empty());
+    assert!(queries::find_orphaned_comments(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn post_counts_by_day_groups_posts_per_day() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    for (day, hour) in [(1, 9), (1, 18), (2, 12), (5, 12)] {
+        let post = common::post(&mut conn, author.user_id(), true);
+        common::set_post_created_at(&mut conn, post.post_id(), common::datetime(2020, 3, day, hour));
+    }
+
+    let from = chrono::NaiveDate::from_ymd_opt(2020, 3, 1).unwrap();
+    let to = chrono::NaiveDate::from_ymd_opt(2020, 3, 3).unwrap();
+    let counts = queries::post_counts_by_day(&mut conn, from, to).unwrap();
+    assert_eq!(
+        counts,
+        vec![
+            (chrono::NaiveDate::from_ymd_opt(2020, 3, 1).unwrap(), 2),
+            (chrono::NaiveDate::from_ymd_opt(2020, 3, 2).unwrap(), 1),
+        ]
+    );
+}
+
+#[test]
+fn sitemap_lists_only_published_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let published = common::post(&mut conn, author.user_id(), true);
+    let draft = common::post(&mut conn, author.user_id(), false);
+
+    let slugs: Vec<String> = queries::get_sitemap_entries(&mut conn).unwrap().into_iter().map(|(slug, _)| slug).collect();
+    assert!(slugs.iter().any(|slug| Some(slug.as_str()) == published.slug()));
+    assert!(slugs.iter().all(|slug| Some(slug.as_str()) != draft.slug()));
+}
+
+#[test]
+fn set_published_for_ids_publishes_only_selected_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let first = common::post(&mut conn, author.user_id(), false);
+    let second = common::post(&mut conn, author.user_id(), false);
+    let third = common::post(&mut conn, author.user_id(), false);
+
+    assert_eq!(queries::set_published_for_ids(&mut conn, &[first.post_id(), second.post_id()], true).unwrap(), 2);
+
+    let published = |conn: &mut brog_app::db::DbConnection, post_id: i32| {
+        queries::resolve_post(conn, &post_id.to_string()).unwrap().unwrap().published()
+    };
+    assert!(published(&mut conn, first.post_id()));
+    assert!(published(&mut conn, second.post_id()));
+    assert!(!published(&mut conn, third.post_id()));
+}
+
+#[test]
+fn get_post_stats_counts_likes_comments_and_views() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let fan = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let other = common::post(&mut conn, author.user_id(), true);
+    queries::toggle_like(&mut conn, author.user_id(), post.post_id()).unwrap();
+    queries::toggle_like(&mut conn, fan.user_id(), post.post_id()).unwrap();
+    queries::toggle_like(&mut conn, fan.user_id(), other.post_id()).unwrap();
+    for _ in 0..3 {
+        common::comment(&mut conn, fan.user_id(), post.post_id());
+    }
+    common::comment(&mut conn, fan.user_id(), other.post_id());
+    for _ in 0..4 {
+        queries::increment_view_count(&mut conn, post.post_id()).unwrap();
+    }
+
+    let stats = queries::get_post_stats(&mut conn, post.post_id()).unwrap();
+    assert_eq!(stats.likes, 2);
+    assert_eq!(stats.comments, 3);
+    assert_eq!(stats.views, 4);
+}
+
+#[test]
+fn increment_view_count_keeps_updated_at() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let edited_at = common::datetime(2024, 1, 1, 0);
+    diesel::update(schema::posts::table.find(post.post_id()))
+        .set(schema::posts::updated_at.eq(edited_at))
+        .execute(&mut conn)
+        .unwrap();
+
+    queries::increment_view_count(&mut conn, post.post_id()).unwrap();
+    let viewed = common::reload_post(&mut conn, post.post_id());
+    assert_eq!(viewed.view_count(), 1);
+    assert_eq!(viewed.updated_at(), edited_at);
+
+    // 閲覧数以外の変更では引き続き更新される
+    queries::update_post(&mut conn, post.post_id(), "Edited", "body", true).unwrap();
+    assert_ne!(common::reload_post(&mut conn, post.post_id()).updated_at(), edited_at);
+}
+
+#[test]
+fn get_posts_excluding_user_omits_that_users_posts() {
+    let mut conn = common::connection();
+    let me = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    common::post(&mut conn, me.user_id(), true);
+    let theirs = common::post(&mut conn, other.user_id(), true);
+
+    let posts = queries::get_posts_excluding_user(&mut conn, me.user_id(), 100, 0).unwrap();
+    assert!(posts.iter().all(|post| post.user_id() != me.user_id()));
+    assert!(posts.iter().any(|post| post.post_id() == theirs.post_id()));
+}
+
+#[test]
+fn swap_post_order_swaps_display_positions() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let first = common::post(&mut conn, author.user_id(), true);
+    let second = common::post(&mut conn, author.user_id(), true);
+
+    queries::swap_post_order(&mut conn, first.post_id(), second.post_id()).unwrap();
+
+    let ids: Vec<i32> = queries::list_posts_by_order(&mut conn, 100, 0)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .filter(|id| *id == first.post_id() || *id == second.post_id())
+        .collect();
+    assert_eq!(ids, vec![second.post_id(), first.post_id()]);
+}
+
+#[test]
+fn list_posts_with_author_name_pairs_each_post_with_its_author() {
+    let mut conn = common::connection();
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+    let alice_post = common::post(&mut conn, alice.user_id(), true);
+    let bob_post = common::post(&mut conn, bob.user_id(), true);
+    common::post(&mut conn, bob.user_id(), false);
+
+    let posts = queries::list_posts_with_author_name(&mut conn, 10, 0).unwrap();
+    assert_eq!(posts.len(), 2);
+    for (post, name) in &posts {
+        if post.post_id() == alice_post.post_id() {
+            assert_eq!(name, alice.name());
+        } else {
+            assert_eq!(post.post_id(), bob_post.post_id());
+            assert_eq!(name, bob.name());
+        }
+    }
+}
+
+#[test]
+fn update_post_versioned_succeeds_with_current_version() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), false);
+
+    let updated = queries::update_post_versioned(&mut conn, post.post_id(), post.version(), "new title", "new body", true).unwrap();
+    assert_eq!(updated.version(), post.version() + 1);
+    assert_eq!(updated.title(), "new title");
+    assert_eq!(updated.post_body(), "new body");
+}
+
+#[test]
+fn update_post_versioned_rejects_stale_version() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), false);
+    queries::update_post_versioned(&mut conn, post.post_id(), post.version(), "first edit", "body", false).unwrap();
+
+    let result = queries::update_post_versioned(&mut conn, post.post_id(), post.version(), "second edit", "body", false);
+    assert!(matches!(result, Err(BrogError::Conflict)));
+    let current = common::reload_post(&mut conn, post.post_id());
+    assert_eq!(current.title(), "first edit");
+    assert_eq!(current.version(), post.version() + 1);
+}
+
+#[test]
+fn for_each_post_visits_every_post_in_batches() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let created: Vec<i32> = (0..1000)
+        .map(|_| common::post(&mut conn, author.user_id(), true).post_id())
+        .collect();
+
+    let mut visited = Vec::new();
+    queries::for_each_post(&mut conn, 100, |post| visited.push(post.post_id())).unwrap();
+    assert_eq!(visited, created);
+}
+
+#[test]
+fn resolve_post_finds_post_by_id_string() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+
+    let found = queries::resolve_post(&mut conn, &post.post_id().to_string()).unwrap().unwrap();
+    assert_eq!(found.post_id(), post.post_id());
+}
+
+#[test]
+fn resolve_post_finds_post_by_slug() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = queries::create_post(&mut conn, "Hello, Slug World", "body", true, author.user_id()).unwrap();
+    let slug = post.slug().unwrap();
+    assert!(slug.starts_with("hello-slug-world"));
+
+    let found = queries::resolve_post(&mut conn, slug).unwrap().unwrap();
+    assert_eq!(found.post_id(), post.post_id());
+    assert!(queries::resolve_post(&mut conn, "no-such-slug").unwrap().is_none());
+}
+
+#[test]
+fn find_posts_with_empty_body_detects_blank_bodies() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let empty = common::post(&mut conn, author.user_id(), false);
+    let blank = common::post(&mut conn, author.user_id(), true);
+    common::post(&mut conn, author.user_id(), true);
+    // インポートの不具合を想定して、検証を通さずに本文を書き換える
+    common::set_post_body(&mut conn, empty.post_id(), "");
+    common::set_post_body(&mut conn, blank.post_id(), " \t\r\n ");
+
+    let post_ids: Vec<i32> = queries::find_posts_with_empty_body(&mut conn)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    assert_eq!(post_ids, vec![empty.post_id(), blank.post_id()]);
+}
+
+#[test]
+fn get_post_years_lists_distinct_years_newest_first() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let first = common::post(&mut conn, author.user_id(), true);
+    let second = common::post(&mut conn, author.user_id(), true);
+    let third = common::post(&mut conn, author.user_id(), true);
+    let draft = common::post(&mut conn, author.user_id(), false);
+    common::set_post_created_at(&mut conn, first.post_id(), common::datetime(2023, 3, 1, 0));
+    common::set_post_created_at(&mut conn, second.post_id(), common::datetime(2024, 6, 1, 0));
+    common::set_post_created_at(&mut conn, third.post_id(), common::datetime(2024, 12, 31, 23));
+    common::set_post_created_at(&mut conn, draft.post_id(), common::datetime(2022, 1, 1, 0));
+
+    assert_eq!(queries::get_post_years(&mut conn).unwrap(), vec![2024, 2023]);
+}
+
+#[test]
+fn get_posts_by_month_selects_posts_created_in_that_month() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let start = common::post(&mut conn, author.user_id(), true);
+    let end = common::post(&mut conn, author.user_id(), true);
+    let before = common::post(&mut conn, author.user_id(), true);
+    let after = common::post(&mut conn, author.user_id(), true);
+    common::set_post_created_at(&mut conn, start.post_id(), common::datetime(2024, 2, 1, 0));
+    common::set_post_created_at(&mut conn, end.post_id(), common::datetime(2024, 2, 29, 23));
+    common::set_post_created_at(&mut conn, before.post_id(), common::datetime(2024, 1, 31, 23));
+    common::set_post_created_at(&mut conn, after.post_id(), common::datetime(2024, 3, 1, 0));
+
+    let post_ids: Vec<i32> = queries::get_posts_by_month(&mut conn, 2024, 2)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    assert_eq!(post_ids, vec![end.post_id(), start.post_id()]);
+    assert!(matches!(queries::get_posts_by_month(&mut conn, 2024, 13), Err(BrogError::Validation(_))));
+}
+
+#[test]
+fn append_to_post_body_joins_additions_with_separator() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = queries::create_post(&mut conn, "Live blog", "Kickoff", true, author.user_id()).unwrap();
+
+    queries::append_to_post_body(&mut conn, post.post_id(), "First update").unwrap();
+    let updated = queries::append_to_post_body(&mut conn, post.post_id(), "Second update").unwrap();
+
+    assert_eq!(updated.post_body(), "Kickoff\n\nFirst update\n\nSecond update");
+    assert_eq!(common::reload_post(&mut conn, post.post_id()).post_body(), updated.post_body());
+}
+
+#[test]
+fn get_post_ids_by_user_returns_only_that_users_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let published = common::post(&mut conn, author.user_id(), true);
+    common::post(&mut conn, other.user_id(), true);
+    let draft = common::post(&mut conn, author.user_id(), false);
+
+    assert_eq!(
+        queries::get_post_ids_by_user(&mut conn, author.user_id()).unwrap(),
+        vec![published.post_id(), draft.post_id()]
+    );
+    let nobody = common::user(&mut conn);
+    assert!(queries::get_post_ids_by_user(&mut conn, nobody.user_id()).unwrap().is_empty());
+}
+
+#[test]
+fn get_moderation_queue_lists_flagged_posts_oldest_first() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let newer = common::post(&mut conn, author.user_id(), false);
+    let older = common::post(&mut conn, author.user_id(), false);
+    common::post(&mut conn, author.user_id(), false);
+    common::flag_for_review(&mut conn, newer.post_id());
+    common::flag_for_review(&mut conn, older.post_id());
+    common::set_post_created_at(&mut conn, newer.post_id(), common::datetime(2024, 2, 1, 0));
+    common::set_post_created_at(&mut conn, older.post_id(), common::datetime(2024, 1, 1, 0));
+
+    let queue: Vec<i32> = queries::get_moderation_queue(&mut conn, 10, 0)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    assert_eq!(queue, vec![older.post_id(), newer.post_id()]);
+}
+
+#[test]
+fn approve_post_publishes_and_removes_from_queue() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), false);
+    common::flag_for_review(&mut conn, post.post_id());
+
+    assert_eq!(queries::approve_post(&mut conn, post.post_id()).unwrap(), 1);
+
+    let approved = common::reload_post(&mut conn, post.post_id());
+    assert!(approved.published());
+    assert!(!approved.needs_review());
+    assert!(queries::get_moderation_queue(&mut conn, 10, 0).unwrap().is_empty());
+}
+
+#[test]
+fn list_posts_after_pages_forward_without_overlap() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let mut expected: Vec<i32> = (0..5).map(|_| common::post(&mut conn, author.user_id(), true).post_id()).collect();
+    common::post(&mut conn, author.user_id(), false);
+    expected.reverse();
+
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = queries::list_posts_after(&mut conn, cursor, 2).unwrap();
+        if page.is_empty() {
+            break;
+        }
+        assert!(page.len() <= 2);
+        cursor = page.last().map(|post| post.post_id());
+        seen.extend(page.iter().map(|post| post.post_id()));
+    }
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn list_posts_after_is_not_shifted_by_new_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let posts: Vec<i32> = (0..3).map(|_| common::post(&mut conn, author.user_id(), true).post_id()).collect();
+
+    let first_page = queries::list_posts_after(&mut conn, None, 1).unwrap();
+    assert_eq!(first_page[0].post_id(), posts[2]);
+    common::post(&mut conn, author.user_id(), true);
+
+    let second_page = queries::list_posts_after(&mut conn, Some(first_page[0].post_id()), 1).unwrap();
+    assert_eq!(second_page[0].post_id(), posts[1]);
+}
+
+#[test]
+fn latest_post_per_author_pairs_each_author_with_newest_post() {
+    let mut conn = common::connection();
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+    let drafter = common::user(&mut conn);
+    let alice_old = common::post(&mut conn, alice.user_id(), true);
+    let alice_new = common::post(&mut conn, alice.user_id(), true);
+    let alice_draft = common::post(&mut conn, alice.user_id(), false);
+    let bob_new = common::post(&mut conn, bob.user_id(), true);
+    let bob_old = common::post(&mut conn, bob.user_id(), true);
+    common::post(&mut conn, drafter.user_id(), false);
+    common::set_post_created_at(&mut conn, alice_old.post_id(), common::datetime(2024, 1, 1, 0));
+    common::set_post_created_at(&mut conn, alice_new.post_id(), common::datetime(2024, 1, 5, 0));
+    common::set_post_created_at(&mut conn, alice_draft.post_id(), common::datetime(2024, 1, 9, 0));
+    common::set_post_created_at(&mut conn, bob_new.post_id(), common::datetime(2024, 1, 3, 0));
+    common::set_post_created_at(&mut conn, bob_old.post_id(), common::datetime(2024, 1, 2, 0));
+
+    let latest: Vec<(i32, i32)> = queries::latest_post_per_author(&mut conn)
+        .unwrap()
+        .iter()
+        .map(|(author, post)| (author.user_id(), post.post_id()))
+        .collect();
+    assert_eq!(latest, vec![(alice.user_id(), alice_new.post_id()), (bob.user_id(), bob_new.post_id())]);
+}
+
+#[test]
+fn latest_post_per_author_ignores_posts_scheduled_for_later() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let visible = common::post(&mut conn, author.user_id(), true);
+    let scheduled = common::post(&mut conn, author.user_id(), true);
+    common::set_post_created_at(&mut conn, visible.post_id(), common::datetime(2024, 1, 1, 0));
+    common::set_post_created_at(&mut conn, scheduled.post_id(), common::datetime(2024, 1, 2, 0));
+    // 公開済みのまま公開日時だけを未来にする
+    diesel::update(schema::posts::table.find(scheduled.post_id()))
+        .set(schema::posts::publish_at.eq(common::datetime(2999, 1, 1, 0)))
+        .execute(&mut conn)
+        .unwrap();
+
+    let latest: Vec<(i32, i32)> = queries::latest_post_per_author(&mut conn)
+        .unwrap()
+        .iter()
+        .map(|(author, post)| (author.user_id(), post.post_id()))
+        .filter(|(user_id, _)| *user_id == author.user_id())
+        .collect();
+    assert_eq!(latest, vec![(author.user_id(), visible.post_id())]);
+}
+
+#[test]
+fn create_post_rejects_publishing_empty_body_but_accepts_draft() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+
+    let result = queries::create_post(&mut conn, "Title", "  ", true, author.user_id());
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    assert!(queries::get_post_ids_by_user(&mut conn, author.user_id()).unwrap().is_empty());
+
+    let draft = queries::create_post(&mut conn, "Title", "", false, author.user_id()).unwrap();
+    assert!(!draft.published());
+}
+
+#[test]
+fn update_post_rejects_publishing_empty_body_draft() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let draft = queries::create_post(&mut conn, "Title", "", false, author.user_id()).unwrap();
+
+    let result = queries::update_post(&mut conn, draft.post_id(), "Title", "", true);
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    assert!(!common::reload_post(&mut conn, draft.post_id()).published());
+}
+
+#[test]
+fn update_post_publishes_complete_draft() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let draft = common::post(&mut conn, author.user_id(), false);
+
+    let published = queries::update_post(&mut conn, draft.post_id(), draft.title(), "finished body", true).unwrap();
+    assert!(published.published());
+}
+
+#[test]
+fn set_published_for_ids_rejects_batch_containing_empty_post() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let complete = common::post(&mut conn, author.user_id(), false);
+    let empty = queries::create_post(&mut conn, "Title", "", false, author.user_id()).unwrap();
+
+    let result = queries::set_published_for_ids(&mut conn, &[complete.post_id(), empty.post_id()], true);
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    assert!(!common::reload_post(&mut conn, complete.post_id()).published());
+    assert!(!common::reload_post(&mut conn, empty.post_id()).published());
+
+    // 非公開にする場合は内容を確認しない
+    assert_eq!(queries::set_published_for_ids(&mut conn, &[empty.post_id()], false).unwrap(), 1);
+}
+
+#[test]
+fn approve_post_rejects_post_without_body() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = queries::create_post(&mut conn, "Title", "", false, author.user_id()).unwrap();
+    common::flag_for_review(&mut conn, post.post_id());
+
+    assert!(matches!(queries::approve_post(&mut conn, post.post_id()), Err(BrogError::Validation(_))));
+    let unchanged = common::reload_post(&mut conn, post.post_id());
+    assert!(!unchanged.published());
+    assert!(unchanged.needs_review());
+}
+
+#[test]
+fn publish_due_posts_skips_posts_without_body() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let complete = common::post(&mut conn, author.user_id(), false);
+    let empty = queries::create_post(&mut conn, "Title", "", false, author.user_id()).unwrap();
+    let due = common::datetime(2024, 1, 1, 0);
+    queries::schedule_post(&mut conn, complete.post_id(), Some(due)).unwrap();
+    queries::schedule_post(&mut conn, empty.post_id(), Some(due)).unwrap();
+
+    assert_eq!(queries::publish_due_posts(&mut conn, common::datetime(2024, 1, 2, 0)).unwrap(), 1);
+    assert!(common::reload_post(&mut conn, complete.post_id()).published());
+    let skipped = common::reload_post(&mut conn, empty.post_id());
+    assert!(!skipped.published());
+    assert_eq!(skipped.publish_at(), Some(due));
+}
+
+#[test]
+fn load_post_detail_bundles_author_counts_and_viewer_like() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let viewer = common::user(&mut conn);
+    let bystander = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    common::comment(&mut conn, viewer.user_id(), post.post_id());
+    common::comment(&mut conn, bystander.user_id(), post.post_id());
+    common::comment(&mut conn, author.user_id(), post.post_id());
+    queries::toggle_like(&mut conn, viewer.user_id(), post.post_id()).unwrap();
+    queries::toggle_like(&mut conn, bystander.user_id(), post.post_id()).unwrap();
+
+    let detail = queries::load_post_detail(&mut conn, post.post_id(), Some(viewer.user_id())).unwrap();
+    assert_eq!(detail.post.post_id(), post.post_id());
+    assert_eq!(detail.author.user_id(), author.user_id());
+    assert_eq!(detail.comment_count, 3);
+    assert_eq!(detail.like_count, 2);
+    assert!(detail.viewer_liked);
+
+    assert!(!queries::load_post_detail(&mut conn, post.post_id(), Some(author.user_id())).unwrap().viewer_liked);
+    assert!(!queries::load_post_detail(&mut conn, post.post_id(), None).unwrap().viewer_liked);
+}
+
+#[test]
+fn delete_empty_drafts_older_than_removes_old_draft_with_dependents() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let reader = common::user(&mut conn);
+    let old_draft = queries::create_post(&mut conn, "Old draft", "", false, author.user_id()).unwrap();
+    let recent_draft = queries::create_post(&mut conn, "Recent draft", "", false, author.user_id()).unwrap();
+    let old_with_body = common::post(&mut conn, author.user_id(), false);
+    common::set_post_created_at(&mut conn, old_draft.post_id(), common::datetime(2024, 1, 1, 0));
+    common::set_post_created_at(&mut conn, recent_draft.post_id(), common::datetime(2024, 3, 1, 0));
+    common::set_post_created_at(&mut conn, old_with_body.post_id(), common::datetime(2024, 1, 1, 0));
+    // 下書きに付いたコメント、返信、メンション、いいね、タグも一緒に削除される
+    let comment = queries::create_comment(&mut conn, reader.user_id(), old_draft.post_id(), &format!("@{}", author.name())).unwrap();
+    queries::create_reply(&mut conn, author.user_id(), comment.comment_id(), "reply").unwrap();
+    queries::toggle_like(&mut conn, reader.user_id(), old_draft.post_id()).unwrap();
+    queries::add_tag_to_post(&mut conn, old_draft.post_id(), &common::unique_name("tag")).unwrap();
+
+    assert_eq!(queries::delete_empty_drafts_older_than(&mut conn, common::datetime(2024, 2, 1, 0)).unwrap(), 1);
+
+    assert_eq!(
+        queries::get_post_ids_by_user(&mut conn, author.user_id()).unwrap(),
+        vec![recent_draft.post_id(), old_with_body.post_id()]
+    );
+    assert!(queries::get_thread_flat(&mut conn, old_draft.post_id()).unwrap().is_empty());
+}
+
+#[test]
+fn search_posts_ranked_puts_title_match_above_body_match() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let body_only = queries::create_post(&mut conn, "Weekly notes", "Some thoughts on ferris the crab", true, author.user_id()).unwrap();
+    let title_only = queries::create_post(&mut conn, "Ferris goes exploring", "A short story", true, author.user_id()).unwrap();
+    let both = queries::create_post(&mut conn, "All about Ferris", "Ferris is the Rust mascot", true, author.user_id()).unwrap();
+    queries::create_post(&mut conn, "Unrelated", "Nothing to see", true, author.user_id()).unwrap();
+
+    let ranked: Vec<(i32, i32)> = queries::search_posts_ranked(&mut conn, "ferris")
+        .unwrap()
+        .iter()
+        .map(|(post, score)| (post.post_id(), *score))
+        .collect();
+    assert_eq!(ranked, vec![(both.post_id(), 3), (title_only.post_id(), 2), (body_only.post_id(), 1)]);
+}
+
+/// 投稿の版のIDを古い順に取得する
+fn revision_ids(conn: &mut DbConnection, post_id: i32) -> Vec<i32> {
+    schema::post_revisions::table
+        .filter(schema::post_revisions::post_id.eq(post_id))
+        .select(schema::post_revisions::revision_id)
+        .order(schema::post_revisions::revision_id.asc())
+        .load(conn)
+        .unwrap()
+}
+
+#[test]
+fn diff_post_revisions_reports_changed_line() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = queries::create_post(&mut conn, "Title", "first\nsecond\nthird", false, author.user_id()).unwrap();
+    queries::update_post(&mut conn, post.post_id(), "Title", "first\nSECOND\nthird", false).unwrap();
+    let revisions = revision_ids(&mut conn, post.post_id());
+    assert_eq!(revisions.len(), 2);
+
+    let diff = queries::diff_post_revisions(&mut conn, revisions[0], revisions[1]).unwrap();
+    assert_eq!(
+        diff,
+        vec![
+            DiffLine::Unchanged("first".to_string()),
+            DiffLine::Removed("second".to_string()),
+            DiffLine::Added("SECOND".to_string()),
+            DiffLine::Unchanged("third".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn diff_post_revisions_rejects_revisions_of_different_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let first = common::post(&mut conn, author.user_id(), false);
+    let second = common::post(&mut conn, author.user_id(), false);
+    let first_revision = revision_ids(&mut conn, first.post_id())[0];
+    let second_revision = revision_ids(&mut conn, second.post_id())[0];
+
+    let result = queries::diff_post_revisions(&mut conn, first_revision, second_revision);
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    assert!(matches!(queries::diff_post_revisions(&mut conn, first_revision, second_revision + 1000), Err(BrogError::NotFound)));
+}
+
+#[test]
+fn find_post_by_title_for_user_matches_trimmed_exact_title() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let post = queries::create_post(&mut conn, "Import Me", "body", false, author.user_id()).unwrap();
+    queries::create_post(&mut conn, "Import Me", "body", false, other.user_id()).unwrap();
+
+    let found = queries::find_post_by_title_for_user(&mut conn, author.user_id(), "  Import Me ").unwrap().unwrap();
+    assert_eq!(found.post_id(), post.post_id());
+}
+
+#[test]
+fn find_post_by_title_for_user_does_not_match_other_titles() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    queries::create_post(&mut conn, "Import Me", "body", false, author.user_id()).unwrap();
+    queries::create_post(&mut conn, "Only Theirs", "body", false, other.user_id()).unwrap();
+
+    for title in ["import me", "IMPORT ME", "Import", "Only Theirs"] {
+        assert!(queries::find_post_by_title_for_user(&mut conn, author.user_id(), title).unwrap().is_none(), "{:?}", title);
+    }
+}
+
+#[test]
+fn get_random_published_posts_returns_distinct_published_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let published: Vec<i32> = (0..3).map(|_| common::post(&mut conn, author.user_id(), true).post_id()).collect();
+    let draft = common::post(&mut conn, author.user_id(), false);
+
+    let sample = queries::get_random_published_posts(&mut conn, 2).unwrap();
+    assert_eq!(sample.len(), 2);
+    assert_ne!(sample[0].post_id(), sample[1].post_id());
+    assert!(sample.iter().all(|post| post.published()));
+
+    let mut all: Vec<i32> = queries::get_random_published_posts(&mut conn, 10)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    all.sort_unstable();
+    let before_dedup = all.len();
+    all.dedup();
+    assert_eq!(all.len(), before_dedup);
+    assert!(published.iter().all(|post_id| all.contains(post_id)));
+    assert!(!all.contains(&draft.post_id()));
+}
+
+#[test]
+fn get_posts_by_users_returns_published_posts_of_selected_users() {
+    let mut conn = common::connection();
+    let first = common::user(&mut conn);
+    let second = common::user(&mut conn);
+    let outsider = common::user(&mut conn);
+    let oldest = common::post(&mut conn, first.user_id(), true);
+    let newest = common::post(&mut conn, second.user_id(), true);
+    let middle = common::post(&mut conn, first.user_id(), true);
+    common::post(&mut conn, second.user_id(), false);
+    common::post(&mut conn, outsider.user_id(), true);
+    common::set_post_created_at(&mut conn, oldest.post_id(), common::datetime(2024, 1, 1, 0));
+    common::set_post_created_at(&mut conn, middle.post_id(), common::datetime(2024, 1, 2, 0));
+    common::set_post_created_at(&mut conn, newest.post_id(), common::datetime(2024, 1, 3, 0));
+
+    let team = [first.user_id(), second.user_id()];
+    let ids: Vec<i32> = queries::get_posts_by_users(&mut conn, &team, 10, 0)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    assert_eq!(ids, vec![newest.post_id(), middle.post_id(), oldest.post_id()]);
+    assert!(queries::get_posts_by_users(&mut conn, &[], 10, 0).unwrap().is_empty());
+}
+
+#[test]
+fn unpublish_posts_older_than_keeps_recent_posts_published() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let old = common::post(&mut conn, author.user_id(), true);
+    let recent = common::post(&mut conn, author.user_id(), true);
+    common::set_post_created_at(&mut conn, old.post_id(), common::datetime(2020, 1, 1, 0));
+    common::set_post_created_at(&mut conn, recent.post_id(), common::datetime(2024, 1, 1, 0));
+
+    assert_eq!(queries::unpublish_posts_older_than(&mut conn, common::datetime(2021, 1, 1, 0)).unwrap(), 1);
+    assert!(!common::reload_post(&mut conn, old.post_id()).published());
+    assert!(common::reload_post(&mut conn, recent.post_id()).published());
+}
+
+#[test]
+fn list_posts_with_top_comment_pairs_oldest_comment_or_none() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let commented = common::post(&mut conn, author.user_id(), true);
+    let quiet = common::post(&mut conn, author.user_id(), true);
+    common::set_post_created_at(&mut conn, commented.post_id(), common::datetime(2024, 1, 1, 0));
+    common::set_post_created_at(&mut conn, quiet.post_id(), common::datetime(2024, 1, 2, 0));
+    let first = common::comment(&mut conn, author.user_id(), commented.post_id());
+    common::comment(&mut conn, author.user_id(), commented.post_id());
+
+    let previews: Vec<(i32, Option<i32>)> = queries::list_posts_with_top_comment(&mut conn, 10, 0)
+        .unwrap()
+        .iter()
+        .filter(|(post, _)| post.user_id() == author.user_id())
+        .map(|(post, comment)| (post.post_id(), comment.as_ref().map(|comment| comment.comment_id())))
+        .collect();
+    assert_eq!(previews, vec![(quiet.post_id(), None), (commented.post_id(), Some(first.comment_id()))]);
+}
+
+#[test]
+fn future_scheduled_post_stays_hidden_until_due() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let scheduled = common::post(&mut conn, author.user_id(), true);
+    let future = common::datetime(2999, 1, 1, 0);
+    queries::schedule_post(&mut conn, scheduled.post_id(), Some(future)).unwrap();
+    assert!(queries::search_posts(&mut conn, scheduled.title()).unwrap().is_empty());
+
+    // 公開済みのままでも公開日時に達していなければ一覧に出ない
+    diesel::update(schema::posts::table.find(scheduled.post_id()))
+        .set(schema::posts::published.eq(true))
+        .execute(&mut conn)
+        .unwrap();
+    assert!(queries::search_posts(&mut conn, scheduled.title()).unwrap().is_empty());
+    assert_eq!(queries::publish_due_posts(&mut conn, common::datetime(2024, 1, 1, 0)).unwrap(), 0);
+    assert_eq!(common::reload_post(&mut conn, scheduled.post_id()).publish_at(), Some(future));
+}
+
+#[test]
+fn publish_due_posts_publishes_post_once_its_time_arrives() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let scheduled = common::post(&mut conn, author.user_id(), false);
+    let not_yet = common::post(&mut conn, author.user_id(), false);
+    queries::schedule_post(&mut conn, scheduled.post_id(), Some(common::datetime(2024, 1, 1, 9))).unwrap();
+    queries::schedule_post(&mut conn, not_yet.post_id(), Some(common::datetime(2024, 1, 1, 11))).unwrap();
+
+    assert_eq!(queries::publish_due_posts(&mut conn, common::datetime(2024, 1, 1, 10)).unwrap(), 1);
+    let published = common::reload_post(&mut conn, scheduled.post_id());
+    assert!(published.published());
+    assert_eq!(published.publish_at(), None);
+    assert_eq!(queries::search_posts(&mut conn, scheduled.title()).unwrap().len(), 1);
+    assert!(!common::reload_post(&mut conn, not_yet.post_id()).published());
+}
+
+#[test]
+fn count_drafts_by_user_counts_unpublished_and_scheduled_posts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    common::post(&mut conn, author.user_id(), false);
+    common::post(&mut conn, author.user_id(), false);
+    let scheduled = common::post(&mut conn, author.user_id(), true);
+    queries::schedule_post(&mut conn, scheduled.post_id(), Some(common::datetime(2999, 1, 1, 0))).unwrap();
+    common::post(&mut conn, author.user_id(), true);
+    common::post(&mut conn, other.user_id(), false);
+
+    assert_eq!(queries::count_drafts_by_user(&mut conn, author.user_id()).unwrap(), 3);
+    assert_eq!(queries::count_drafts_by_user(&mut conn, -1).unwrap(), 0);
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::DbConnection;
+use brog_app::email::Email;
+use brog_app::error::BrogError;
+use brog_app::service::{BrogService, DbPool};
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
+use diesel::Connection;
+
+/// 取り出した接続でテスト用トランザクションを開始するカスタマイザー
+#[derive(Debug)]
+struct TestTransaction;
+
+impl CustomizeConnection<DbConnection, diesel::r2d2::Error> for TestTransaction {
+    fn on_acquire(&self, conn: &mut DbConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.begin_test_transaction().map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// 変更がロールバックされる接続を1つだけ持つプールを作成する
+fn test_pool() -> DbPool {
+    // マイグレーションを適用するため、共通モジュールの接続を一度取得する
+    drop(common::connection());
+    Pool::builder()
+        .max_size(1)
+        .connection_customizer(Box::new(TestTransaction))
+        .build(ConnectionManager::<DbConnection>::new(common::database_url()))
+        .expect("failed to build pool")
+}
+
+#[test]
+fn service_registers_and_loads_user_through_pool() {
+    let service = BrogService::new(test_pool());
+    let name = common::unique_name("service");
+    let email = Email::try_from(format!("{}@example.com", name).as_str()).unwrap();
+
+    let (user, welcome_post) = service.register_user(&name, &email, "long-enough").unwrap();
+    assert_eq!(welcome_post.user_id(), user.user_id());
+
+    let shared = service.clone();
+    assert_eq!(shared.get_user_by_id(user.user_id()).unwrap().name(), name);
+    assert_eq!(shared.get_active_user_by_email(&email).unwrap().unwrap().user_id(), user.user_id());
+}
+
+#[test]
+fn service_reports_missing_user_as_not_found() {
+    let service = BrogService::new(test_pool());
+    assert!(matches!(service.get_user_by_id(-1), Err(BrogError::NotFound)));
+}
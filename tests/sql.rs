@@ -0,0 +1,42 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::{queries, schema, sql};
+use brog_app::email::Email;
+use diesel::prelude::*;
+
+#[test]
+fn lower_converts_text_to_lowercase() {
+    let mut conn = common::connection();
+
+    let lowered: String = diesel::select(sql::lower("MiXeD Case")).get_result(&mut conn).unwrap();
+    assert_eq!(lowered, "mixed case");
+}
+
+#[test]
+fn get_user_by_email_matches_stored_address_case_insensitively() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    // 小文字に揃える前に登録されたアドレスを想定して、大文字を含むアドレスに書き換える
+    diesel::update(schema::users::table.find(user.user_id()))
+        .set(schema::users::email.eq(user.email().to_uppercase()))
+        .execute(&mut conn)
+        .unwrap();
+
+    let email = Email::try_from(user.email()).unwrap();
+    let found = queries::get_user_by_email(&mut conn, &email).unwrap().unwrap();
+    assert_eq!(found.user_id(), user.user_id());
+}
+
+#[test]
+fn search_posts_matches_regardless_of_case() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let post = queries::create_post(&mut conn, "Learning Rust", "Ownership And Borrowing", true, author.user_id()).unwrap();
+
+    for query in ["rust", "RUST", "ownership and BORROWING"] {
+        let found = queries::search_posts(&mut conn, query).unwrap();
+        assert_eq!(found.iter().map(|post| post.post_id()).collect::<Vec<_>>(), vec![post.post_id()], "query {:?}", query);
+    }
+}
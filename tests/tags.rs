@@ -0,0 +1,83 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::queries;
+
+#[test]
+fn add_tag_to_posts_skips_posts_that_already_have_the_tag() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let tag = queries::create_tag(&mut conn, &common::unique_name("tag")).unwrap();
+    let tagged = common::post(&mut conn, author.user_id(), true);
+    let second = common::post(&mut conn, author.user_id(), true);
+    let third = common::post(&mut conn, author.user_id(), true);
+    queries::add_tag_to_posts(&mut conn, tag.tag_id(), &[tagged.post_id()]).unwrap();
+
+    let inserted = queries::add_tag_to_posts(&mut conn, tag.tag_id(), &[tagged.post_id(), second.post_id(), third.post_id()]).unwrap();
+    assert_eq!(inserted, 2);
+
+    let mut post_ids: Vec<i32> = queries::get_posts_by_tag(&mut conn, tag.name())
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    post_ids.sort();
+    assert_eq!(post_ids, vec![tagged.post_id(), second.post_id(), third.post_id()]);
+}
+
+#[test]
+fn get_untagged_posts_returns_only_published_posts_without_tags() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let tagged = common::post(&mut conn, author.user_id(), true);
+    let untagged = common::post(&mut conn, author.user_id(), true);
+    common::post(&mut conn, author.user_id(), false);
+    queries::add_tag_to_post(&mut conn, tagged.post_id(), &common::unique_name("tag")).unwrap();
+
+    let post_ids: Vec<i32> = queries::get_untagged_posts(&mut conn, 10, 0)
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    assert_eq!(post_ids, vec![untagged.post_id()]);
+}
+
+#[test]
+fn differently_cased_tag_names_resolve_to_one_tag() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let first = common::post(&mut conn, author.user_id(), true);
+    let second = common::post(&mut conn, author.user_id(), true);
+    let name = common::unique_name("Rust");
+
+    let tag = queries::add_tag_to_post(&mut conn, first.post_id(), &name).unwrap();
+    let same = queries::add_tag_to_post(&mut conn, second.post_id(), &format!("  {}  ", name.to_uppercase())).unwrap();
+    assert_eq!(same.tag_id(), tag.tag_id());
+    assert_eq!(tag.name(), name.to_lowercase());
+
+    let mut post_ids: Vec<i32> = queries::get_posts_by_tag(&mut conn, &name.to_uppercase())
+        .unwrap()
+        .iter()
+        .map(|post| post.post_id())
+        .collect();
+    post_ids.sort();
+    assert_eq!(post_ids, vec![first.post_id(), second.post_id()]);
+}
+
+#[test]
+fn get_post_ids_by_tag_lists_tagged_posts_including_drafts() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let tag = queries::create_tag(&mut conn, &common::unique_name("tag")).unwrap();
+    let published = common::post(&mut conn, author.user_id(), true);
+    let draft = common::post(&mut conn, author.user_id(), false);
+    common::post(&mut conn, author.user_id(), true);
+    queries::add_tag_to_posts(&mut conn, tag.tag_id(), &[draft.post_id(), published.post_id()]).unwrap();
+
+    assert_eq!(
+        queries::get_post_ids_by_tag(&mut conn, tag.tag_id()).unwrap(),
+        vec![published.post_id(), draft.post_id()]
+    );
+    assert!(queries::get_post_ids_by_tag(&mut conn, -1).unwrap().is_empty());
+}
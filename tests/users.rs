@@ -0,0 +1,539 @@
+#![cfg(feature = "live-db")]
+
+mod common;
+
+use brog_app::db::{queries, schema};
+use brog_app::email::Email;
+use brog_app::error::BrogError;
+use brog_app::models;
+use brog_app::password;
+use diesel::prelude::*;
+
+#[test]
+fn get_user_by_name_finds_existing_user() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+
+    let found = queries::get_user_by_name(&mut conn, user.name()).unwrap().unwrap();
+    assert_eq!(found.user_id(), user.user_id());
+}
+
+#[test]
+fn get_user_by_name_returns_none_for_unknown_name() {
+    let mut conn = common::connection();
+    common::user(&mut conn);
+
+    let name = common::unique_name("nobody");
+    assert!(queries::get_user_by_name(&mut conn, &name).unwrap().is_none());
+}
+
+#[test]
+fn register_user_creates_user_and_welcome_post() {
+    let mut conn = common::connection();
+    let name = common::unique_name("register");
+    let email = Email::try_from(format!("{}@example.com", name).as_str()).unwrap();
+
+    let (user, post) = queries::register_user(&mut conn, &name, &email, "password123").unwrap();
+    assert_eq!(user.name(), name);
+    assert!(password::verify_password("password123", user.password_hash()).unwrap());
+    assert_eq!(post.user_id(), user.user_id());
+    assert!(post.published());
+}
+
+// MySQLではDDLが暗黙的にコミットされるため、テスト用トランザクション内で制約を追加できるPostgreSQLでのみ実行する
+#[cfg(feature = "postgres")]
+#[test]
+fn register_user_persists_nothing_when_welcome_post_fails() {
+    let mut conn = common::connection();
+    diesel::sql_query("ALTER TABLE posts ADD CONSTRAINT reject_welcome_posts CHECK (title <> 'Welcome to BrogApp!') NOT VALID")
+        .execute(&mut conn)
+        .unwrap();
+    let name = common::unique_name("register");
+    let email = Email::try_from(format!("{}@example.com", name).as_str()).unwrap();
+
+    let result = queries::register_user(&mut conn, &name, &email, "password123");
+    assert!(matches!(result, Err(BrogError::Database(_))));
+    assert!(queries::get_user_by_name(&mut conn, &name).unwrap().is_none());
+    assert!(queries::get_user_by_email(&mut conn, &email).unwrap().is_none());
+}
+
+#[test]
+fn find_duplicate_emails_reports_shared_address() {
+    let mut conn = common::connection();
+    let email = Email::try_from(format!("{}@example.com", common::unique_name("dup")).as_str()).unwrap();
+    queries::create_user(&mut conn, &common::unique_name("first"), &email, "hash").unwrap();
+    queries::create_user(&mut conn, &common::unique_name("second"), &email, "hash").unwrap();
+    let single = common::user(&mut conn);
+
+    let duplicates = queries::find_duplicate_emails(&mut conn).unwrap();
+    assert!(duplicates.contains(&(email.as_str().to_string(), 2)));
+    assert!(duplicates.iter().all(|(address, _)| address != single.email()));
+}
+
+#[test]
+fn password_reset_full_flow() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    // 大文字で入力されたメールアドレスでも登録済みのユーザーを見つける
+    let typed = Email::try_from(user.email().to_uppercase().as_str()).unwrap();
+
+    let token = queries::request_password_reset(&mut conn, &typed).unwrap();
+    // トークンそのものは保存しない
+    let stored: Vec<String> = schema::password_resets::table
+        .filter(schema::password_resets::user_id.eq(user.user_id()))
+        .select(schema::password_resets::token)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_ne!(stored[0], token);
+
+    queries::reset_password(&mut conn, &token, "new-password").unwrap();
+    let updated = queries::get_user_by_id(&mut conn, user.user_id()).unwrap();
+    assert!(password::verify_password("new-password", updated.password_hash()).unwrap());
+}
+
+#[test]
+fn password_reset_with_expired_token_is_rejected() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let email = Email::try_from(user.email()).unwrap();
+    let token = queries::request_password_reset(&mut conn, &email).unwrap();
+    diesel::update(schema::password_resets::table.filter(schema::password_resets::user_id.eq(user.user_id())))
+        .set(schema::password_resets::expires_at.eq(common::datetime(2020, 1, 1, 0)))
+        .execute(&mut conn)
+        .unwrap();
+
+    let result = queries::reset_password(&mut conn, &token, "new-password");
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    let unchanged = queries::get_user_by_id(&mut conn, user.user_id()).unwrap();
+    assert_eq!(unchanged.password_hash().as_str(), user.password_hash().as_str());
+}
+
+#[test]
+fn password_reset_token_cannot_be_reused() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let email = Email::try_from(user.email()).unwrap();
+    let token = queries::request_password_reset(&mut conn, &email).unwrap();
+
+    queries::reset_password(&mut conn, &token, "first-password").unwrap();
+    let result = queries::reset_password(&mut conn, &token, "second-password");
+    assert!(matches!(result, Err(BrogError::Validation(_))));
+    let updated = queries::get_user_by_id(&mut conn, user.user_id()).unwrap();
+    assert!(password::verify_password("first-password", updated.password_hash()).unwrap());
+}
+
+#[test]
+fn password_reset_with_unknown_token_is_not_found() {
+    let mut conn = common::connection();
+    let result = queries::reset_password(&mut conn, "no-such-token", "new-password");
+    assert!(matches!(result, Err(BrogError::NotFound)));
+}
+
+#[test]
+fn password_reset_for_unknown_email_is_not_found() {
+    let mut conn = common::connection();
+    let email = Email::try_from(format!("{}@example.com", common::unique_name("nobody")).as_str()).unwrap();
+    let result = queries::request_password_reset(&mut conn, &email);
+    assert!(matches!(result, Err(diesel::result::Error::NotFound)));
+}
+
+#[test]
+fn find_users_with_no_activity_skips_posters_and_commenters() {
+    let mut conn = common::connection();
+    let poster = common::user(&mut conn);
+    let commenter = common::user(&mut conn);
+    let idle = common::user(&mut conn);
+    let post = common::post(&mut conn, poster.user_id(), true);
+    common::comment(&mut conn, commenter.user_id(), post.post_id());
+
+    let ids: Vec<i32> = queries::find_users_with_no_activity(&mut conn)
+        .unwrap()
+        .iter()
+        .map(|user| user.user_id())
+        .collect();
+    assert!(ids.contains(&idle.user_id()));
+    assert!(!ids.contains(&poster.user_id()));
+    assert!(!ids.contains(&commenter.user_id()));
+}
+
+#[test]
+fn find_user_by_id_returns_existing_user() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+
+    let found = queries::find_user_by_id(&mut conn, user.user_id()).unwrap().unwrap();
+    assert_eq!(found.name(), user.name());
+}
+
+#[test]
+fn find_user_by_id_returns_none_for_unknown_id() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+
+    assert!(queries::find_user_by_id(&mut conn, user.user_id() + 1000).unwrap().is_none());
+}
+
+#[test]
+fn touched_user_appears_in_recently_active_users() {
+    let mut conn = common::connection();
+    let active = common::user(&mut conn);
+    let idle = common::user(&mut conn);
+    assert!(active.last_active_at().is_none());
+
+    queries::touch_last_active(&mut conn, active.user_id()).unwrap();
+
+    let touched = queries::get_user_by_id(&mut conn, active.user_id()).unwrap();
+    assert!(touched.last_active_at().is_some());
+    let recent: Vec<i32> = queries::get_recently_active_users(&mut conn, common::datetime(2000, 1, 1, 0), 10)
+        .unwrap()
+        .iter()
+        .map(|user| user.user_id())
+        .collect();
+    assert!(recent.contains(&active.user_id()));
+    assert!(!recent.contains(&idle.user_id()));
+}
+
+#[test]
+fn recently_active_users_excludes_activity_before_since() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    queries::touch_last_active(&mut conn, user.user_id()).unwrap();
+    let last_active_at = queries::get_user_by_id(&mut conn, user.user_id()).unwrap().last_active_at().unwrap();
+
+    let recent = queries::get_recently_active_users(&mut conn, last_active_at + chrono::Duration::seconds(1), 10).unwrap();
+    assert!(recent.iter().all(|recent_user| recent_user.user_id() != user.user_id()));
+}
+
+#[test]
+fn delete_user_impact_counts_posts_and_comments_without_deleting() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let first = common::post(&mut conn, user.user_id(), true);
+    common::post(&mut conn, user.user_id(), false);
+    let other_post = common::post(&mut conn, other.user_id(), true);
+    common::comment(&mut conn, user.user_id(), first.post_id());
+    common::comment(&mut conn, user.user_id(), other_post.post_id());
+    common::comment(&mut conn, user.user_id(), other_post.post_id());
+    common::comment(&mut conn, other.user_id(), first.post_id());
+
+    let impact = queries::delete_user_impact(&mut conn, user.user_id()).unwrap();
+    assert_eq!(impact.posts, 2);
+    assert_eq!(impact.comments, 3);
+    assert!(queries::find_user_by_id(&mut conn, user.user_id()).unwrap().is_some());
+}
+
+#[test]
+fn get_user_timeline_interleaves_posts_and_comments_newest_first() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let old_post = common::post(&mut conn, user.user_id(), true);
+    let new_post = common::post(&mut conn, user.user_id(), true);
+    let other_post = common::post(&mut conn, other.user_id(), true);
+    let draft = common::post(&mut conn, user.user_id(), false);
+    let scheduled = common::post(&mut conn, user.user_id(), true);
+    queries::schedule_post(&mut conn, scheduled.post_id(), Some(common::datetime(2999, 1, 1, 0))).unwrap();
+    let old_comment = common::comment(&mut conn, user.user_id(), other_post.post_id());
+    let new_comment = common::comment(&mut conn, user.user_id(), other_post.post_id());
+    common::comment(&mut conn, other.user_id(), new_post.post_id());
+    common::set_post_created_at(&mut conn, old_post.post_id(), common::datetime(2024, 1, 1, 0));
+    common::set_comment_created_at(&mut conn, old_comment.comment_id(), common::datetime(2024, 1, 2, 0));
+    common::set_post_created_at(&mut conn, new_post.post_id(), common::datetime(2024, 1, 3, 0));
+    common::set_comment_created_at(&mut conn, new_comment.comment_id(), common::datetime(2024, 1, 4, 0));
+    common::set_post_created_at(&mut conn, draft.post_id(), common::datetime(2024, 1, 5, 0));
+    common::set_post_created_at(&mut conn, scheduled.post_id(), common::datetime(2024, 1, 5, 0));
+
+    let timeline: Vec<String> = queries::get_user_timeline(&mut conn, user.user_id(), 10)
+        .unwrap()
+        .iter()
+        .map(|item| match item {
+            models::TimelineItem::Post(post) => format!("post {}", post.post_id()),
+            models::TimelineItem::Comment(comment) => format!("comment {}", comment.comment_id()),
+        })
+        .collect();
+    assert_eq!(
+        timeline,
+        vec![
+            format!("comment {}", new_comment.comment_id()),
+            format!("post {}", new_post.post_id()),
+            format!("comment {}", old_comment.comment_id()),
+            format!("post {}", old_post.post_id()),
+        ]
+    );
+
+    let limited = queries::get_user_timeline(&mut conn, user.user_id(), 2).unwrap();
+    assert_eq!(limited.len(), 2);
+    assert_eq!(limited[0].created_at(), common::datetime(2024, 1, 4, 0));
+}
+
+#[test]
+fn delete_user_preserving_content_moves_content_to_placeholder() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let placeholder = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    let post = common::post(&mut conn, user.user_id(), true);
+    let other_post = common::post(&mut conn, other.user_id(), true);
+    let comment = common::comment(&mut conn, user.user_id(), other_post.post_id());
+    queries::follow_user(&mut conn, user.user_id(), other.user_id()).unwrap();
+    queries::toggle_like(&mut conn, user.user_id(), other_post.post_id()).unwrap();
+
+    queries::delete_user_preserving_content(&mut conn, user.user_id(), placeholder.user_id()).unwrap();
+
+    assert!(queries::find_user_by_id(&mut conn, user.user_id()).unwrap().is_none());
+    assert_eq!(common::reload_post(&mut conn, post.post_id()).user_id(), placeholder.user_id());
+    let moved_comment: models::Comment = schema::comments::table.find(comment.comment_id()).first(&mut conn).unwrap();
+    assert_eq!(moved_comment.user_id(), placeholder.user_id());
+}
+
+#[test]
+fn delete_user_preserving_content_requires_existing_placeholder() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    common::post(&mut conn, user.user_id(), true);
+
+    let result = queries::delete_user_preserving_content(&mut conn, user.user_id(), user.user_id() + 1000);
+    assert!(matches!(result, Err(BrogError::NotFound)));
+    assert!(queries::find_user_by_id(&mut conn, user.user_id()).unwrap().is_some());
+}
+
+/// ユーザーへのメンションの数を数える
+fn mention_count(conn: &mut brog_app::db::DbConnection, user_id: i32) -> i64 {
+    schema::comment_mentions::table
+        .filter(schema::comment_mentions::mentioned_user_id.eq(user_id))
+        .count()
+        .get_result(conn)
+        .unwrap()
+}
+
+#[test]
+fn mentioned_user_can_be_deleted() {
+    let mut conn = common::connection();
+    let mentioned = common::user(&mut conn);
+    let author = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let comment = queries::create_comment(&mut conn, author.user_id(), post.post_id(), &format!("thanks @{}", mentioned.name())).unwrap();
+    assert_eq!(mention_count(&mut conn, mentioned.user_id()), 1);
+
+    assert_eq!(queries::delete_user(&mut conn, mentioned.user_id()).unwrap(), 1);
+    assert_eq!(mention_count(&mut conn, mentioned.user_id()), 0);
+    assert!(schema::comments::table.find(comment.comment_id()).first::<models::Comment>(&mut conn).is_ok());
+}
+
+#[test]
+fn delete_user_preserving_content_removes_mentions_of_user() {
+    let mut conn = common::connection();
+    let mentioned = common::user(&mut conn);
+    let placeholder = common::user(&mut conn);
+    let author = common::user(&mut conn);
+    common::post(&mut conn, mentioned.user_id(), true);
+    let post = common::post(&mut conn, author.user_id(), true);
+    queries::create_comment(&mut conn, author.user_id(), post.post_id(), &format!("@{} agreed", mentioned.name())).unwrap();
+
+    queries::delete_user_preserving_content(&mut conn, mentioned.user_id(), placeholder.user_id()).unwrap();
+    assert!(queries::find_user_by_id(&mut conn, mentioned.user_id()).unwrap().is_none());
+    assert_eq!(mention_count(&mut conn, mentioned.user_id()), 0);
+}
+
+#[test]
+fn user_signups_by_day_groups_users_by_registration_date() {
+    let mut conn = common::connection();
+    let first = common::user(&mut conn);
+    let second = common::user(&mut conn);
+    let third = common::user(&mut conn);
+    let outside = common::user(&mut conn);
+    common::set_user_created_at(&mut conn, first.user_id(), common::datetime(2024, 3, 1, 9));
+    common::set_user_created_at(&mut conn, second.user_id(), common::datetime(2024, 3, 1, 23));
+    common::set_user_created_at(&mut conn, third.user_id(), common::datetime(2024, 3, 3, 0));
+    common::set_user_created_at(&mut conn, outside.user_id(), common::datetime(2024, 3, 4, 0));
+
+    let day = |d| chrono::NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+    assert_eq!(
+        queries::user_signups_by_day(&mut conn, day(1), day(3)).unwrap(),
+        vec![(day(1), 2), (day(3), 1)]
+    );
+}
+
+#[test]
+fn name_map_by_emails_maps_found_emails_and_omits_missing() {
+    let mut conn = common::connection();
+    let alice = common::user(&mut conn);
+    let bob = common::user(&mut conn);
+    let alice_upper = alice.email().to_uppercase();
+    let missing = format!("{}@example.com", common::unique_name("missing"));
+
+    let names = queries::name_map_by_emails(&mut conn, &[alice_upper.as_str(), bob.email(), missing.as_str()]).unwrap();
+    assert_eq!(names.len(), 2);
+    assert_eq!(names[&alice_upper], alice.name());
+    assert_eq!(names[bob.email()], bob.name());
+    assert!(!names.contains_key(&missing));
+}
+
+#[cfg(feature = "unique-usernames")]
+#[test]
+fn create_user_rejects_taken_name() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let email = Email::try_from(format!("{}@example.com", common::unique_name("other")).as_str()).unwrap();
+
+    let result = queries::create_user(&mut conn, user.name(), &email, "hash");
+    assert!(matches!(result, Err(BrogError::DuplicateName)));
+    assert!(queries::get_user_by_email(&mut conn, &email).unwrap().is_none());
+}
+
+#[cfg(feature = "unique-usernames")]
+#[test]
+fn unique_index_rejects_taken_name_inserted_directly() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let duplicate = models::NewUser {
+        name: user.name().to_string(),
+        email: format!("{}@example.com", common::unique_name("other")),
+        password_hash: password::PasswordHash::new("hash".to_string()),
+    };
+
+    let result = diesel::insert_into(schema::users::table).values(&duplicate).execute(&mut conn);
+    assert!(matches!(
+        result,
+        Err(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _))
+    ));
+}
+
+#[test]
+fn get_users_registered_between_includes_both_end_dates() {
+    let mut conn = common::connection();
+    let before = common::user(&mut conn);
+    let first_day = common::user(&mut conn);
+    let last_day = common::user(&mut conn);
+    let after = common::user(&mut conn);
+    common::set_user_created_at(&mut conn, before.user_id(), common::datetime(2024, 4, 30, 23));
+    common::set_user_created_at(&mut conn, first_day.user_id(), common::datetime(2024, 5, 1, 0));
+    common::set_user_created_at(&mut conn, last_day.user_id(), common::datetime(2024, 5, 10, 23));
+    common::set_user_created_at(&mut conn, after.user_id(), common::datetime(2024, 5, 11, 0));
+
+    let day = |d| chrono::NaiveDate::from_ymd_opt(2024, 5, d).unwrap();
+    let user_ids: Vec<i32> = queries::get_users_registered_between(&mut conn, day(1), day(10))
+        .unwrap()
+        .iter()
+        .map(|user| user.user_id())
+        .collect();
+    assert_eq!(user_ids, vec![first_day.user_id(), last_day.user_id()]);
+}
+
+#[test]
+fn deactivated_user_is_hidden_and_reactivation_restores() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let post = common::post(&mut conn, user.user_id(), true);
+    let email = Email::try_from(user.email()).unwrap();
+    let listed = |conn: &mut brog_app::db::DbConnection| {
+        queries::list_posts_with_author_name(conn, 10, 0)
+            .unwrap()
+            .iter()
+            .any(|(listed, _)| listed.post_id() == post.post_id())
+    };
+    assert!(listed(&mut conn));
+
+    assert_eq!(queries::deactivate_user(&mut conn, user.user_id()).unwrap(), 1);
+    assert!(!listed(&mut conn));
+    assert!(queries::search_posts(&mut conn, post.title()).unwrap().is_empty());
+    assert!(queries::get_active_user_by_email(&mut conn, &email).unwrap().is_none());
+    assert!(queries::get_user_by_email(&mut conn, &email).unwrap().is_some());
+
+    assert_eq!(queries::reactivate_user(&mut conn, user.user_id()).unwrap(), 1);
+    assert!(listed(&mut conn));
+    assert_eq!(queries::search_posts(&mut conn, post.title()).unwrap().len(), 1);
+    assert!(queries::get_active_user_by_email(&mut conn, &email).unwrap().is_some());
+}
+
+#[test]
+fn get_digest_recipients_lists_recent_active_commenters_once() {
+    let mut conn = common::connection();
+    let author = common::user(&mut conn);
+    let regular = common::user(&mut conn);
+    let deactivated = common::user(&mut conn);
+    let muted = common::user(&mut conn);
+    let lapsed = common::user(&mut conn);
+    let post = common::post(&mut conn, author.user_id(), true);
+    let since = common::datetime(2024, 7, 1, 0);
+    for (user, created_at) in [
+        (&regular, common::datetime(2024, 7, 2, 0)),
+        (&regular, common::datetime(2024, 7, 3, 0)),
+        (&deactivated, common::datetime(2024, 7, 2, 0)),
+        (&muted, common::datetime(2024, 7, 2, 0)),
+        (&lapsed, common::datetime(2024, 6, 30, 0)),
+    ] {
+        let comment = common::comment(&mut conn, user.user_id(), post.post_id());
+        common::set_comment_created_at(&mut conn, comment.comment_id(), created_at);
+    }
+    queries::deactivate_user(&mut conn, deactivated.user_id()).unwrap();
+    common::set_notifications_enabled(&mut conn, muted.user_id(), false);
+
+    let recipients: Vec<i32> = queries::get_digest_recipients(&mut conn, since)
+        .unwrap()
+        .iter()
+        .map(|user| user.user_id())
+        .collect();
+    assert_eq!(recipients, vec![regular.user_id()]);
+}
+
+#[test]
+fn get_profile_header_collects_name_counts_and_join_date() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    let follower = common::user(&mut conn);
+    let other = common::user(&mut conn);
+    diesel::update(schema::users::table.find(user.user_id()))
+        .set(schema::users::display_name.eq("Display Name"))
+        .execute(&mut conn)
+        .unwrap();
+    let joined_at = common::datetime(2024, 1, 15, 9);
+    common::set_user_created_at(&mut conn, user.user_id(), joined_at);
+    let post = common::post(&mut conn, user.user_id(), true);
+    common::post(&mut conn, user.user_id(), true);
+    common::post(&mut conn, user.user_id(), false);
+    common::comment(&mut conn, user.user_id(), post.post_id());
+    common::comment(&mut conn, follower.user_id(), post.post_id());
+    queries::follow_user(&mut conn, follower.user_id(), user.user_id()).unwrap();
+    queries::follow_user(&mut conn, user.user_id(), other.user_id()).unwrap();
+
+    let header = queries::get_profile_header(&mut conn, user.user_id()).unwrap();
+    assert_eq!(header.user_id, user.user_id());
+    assert_eq!(header.name, user.name());
+    assert_eq!(header.display_name.as_deref(), Some("Display Name"));
+    assert_eq!(header.post_count, 2);
+    assert_eq!(header.comment_count, 1);
+    assert_eq!(header.follower_count, 1);
+    assert_eq!(header.joined_at, joined_at);
+    assert!(matches!(queries::get_profile_header(&mut conn, -1), Err(diesel::result::Error::NotFound)));
+}
+
+#[test]
+fn set_avatar_url_sets_and_clears_url() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+
+    assert_eq!(queries::set_avatar_url(&mut conn, user.user_id(), Some("https://example.com/a.png")).unwrap(), 1);
+    let updated = queries::get_user_by_id(&mut conn, user.user_id()).unwrap();
+    assert_eq!(updated.avatar_url(), Some("https://example.com/a.png"));
+
+    assert_eq!(queries::set_avatar_url(&mut conn, user.user_id(), None).unwrap(), 1);
+    assert_eq!(queries::get_user_by_id(&mut conn, user.user_id()).unwrap().avatar_url(), None);
+}
+
+#[test]
+fn set_avatar_url_rejects_non_http_url() {
+    let mut conn = common::connection();
+    let user = common::user(&mut conn);
+    queries::set_avatar_url(&mut conn, user.user_id(), Some("https://example.com/a.png")).unwrap();
+
+    for url in ["ftp://example.com/a.png", "javascript:alert(1)", "https://", "https://example.com/a b.png"] {
+        assert!(matches!(queries::set_avatar_url(&mut conn, user.user_id(), Some(url)), Err(BrogError::Validation(_))), "{:?}", url);
+    }
+    assert_eq!(
+        queries::get_user_by_id(&mut conn, user.user_id()).unwrap().avatar_url(),
+        Some("https://example.com/a.png")
+    );
+}